@@ -0,0 +1,451 @@
+//! Minecraft 1.12 Anvil region files, `r.{x}.{z}.mca`, each holding up to 32×32 chunks.
+//!
+//! A region starts with 1024 big-endian location entries (3 bytes sector offset, 1 byte sector
+//! count) and 1024 timestamps. A chunk lives at its offset as a 4 byte length, a compression byte
+//! and a compressed NBT document whose `Level.Sections` hold the blocks.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::{Block, BlockPosition, BlockState, ChunkData, ChunkPosition, Section, Sections};
+use crate::registry::{BlockRegistry, RegistryState};
+use crate::nbt::{compound, NbtError, Tag};
+use crate::world::{World, WorldLoadError};
+
+pub const SECTOR_SIZE: usize = 4096;
+/// Data version of 1.12.2, so newer servers know to upgrade what we write.
+pub const DATA_VERSION: i32 = 1343;
+pub const REGION_SIZE: i32 = 32;
+const HEADER_SIZE: usize = 2 * SECTOR_SIZE;
+
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+
+#[derive(Debug)]
+pub enum AnvilError {
+    Io(std::io::Error),
+    Nbt(NbtError),
+    /// The file is shorter than the location and timestamp tables.
+    Truncated,
+    /// The location entry of the `index`-th chunk points outside the file.
+    BadLocation { index: usize },
+    UnknownCompression(u8),
+    MissingTag(&'static str),
+    /// A section with a `Y` outside the 24 sections of a `ChunkData`, or with short arrays.
+    BadSection { y: i64 },
+    UnknownBlock { id: u16, position: BlockPosition },
+    /// 1.12 only has 16 sections, the chunk has blocks in the ones above.
+    AboveBuildLimit(ChunkPosition),
+    /// The compressed chunk needs more than the 255 sectors a location entry can address.
+    ChunkTooLarge(ChunkPosition),
+}
+
+impl Display for AnvilError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Nbt(e) => write!(f, "nbt error: {}", e),
+            Self::Truncated => f.write_str("region file is shorter than its header"),
+            Self::BadLocation { index } => write!(f, "chunk {} points outside the region file", index),
+            Self::UnknownCompression(c) => write!(f, "unknown compression type {}", c),
+            Self::MissingTag(name) => write!(f, "missing or mistyped tag {}", name),
+            Self::BadSection { y } => write!(f, "invalid section at Y={}", y),
+            Self::UnknownBlock { id, position } => write!(f, "unknown block id {} at {:?}", id, position),
+            Self::AboveBuildLimit(pos) => write!(f, "chunk {:?} has blocks above y=255", pos),
+            Self::ChunkTooLarge(pos) => write!(f, "chunk {:?} is too large for a region file", pos),
+        }
+    }
+}
+
+impl std::error::Error for AnvilError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Nbt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for AnvilError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<NbtError> for AnvilError {
+    fn from(e: NbtError) -> Self {
+        Self::Nbt(e)
+    }
+}
+
+/// Parses the region coordinates out of an `r.{x}.{z}.mca` file name.
+pub fn parse_region_file_name(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?.strip_prefix("r.")?.strip_suffix(".mca")?;
+    let (x, z) = name.split_once('.')?;
+    Some((x.parse().ok()?, z.parse().ok()?))
+}
+
+fn chunk_index(x: i32, z: i32) -> usize {
+    (x.rem_euclid(REGION_SIZE) + z.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+}
+
+/// The 4-bit entry `i` of a nibble array, low nibble first.
+pub(crate) fn nibble(array: &[u8], i: usize) -> u8 {
+    (array[i >> 1] >> ((i & 1) * 4)) & 0x0f
+}
+
+/// Inverse of [`nibble`], `values` must have an even length.
+pub(crate) fn pack_nibbles(values: impl Iterator<Item=u8>) -> Vec<u8> {
+    let values = values.collect::<Vec<_>>();
+    values.chunks_exact(2).map(|v| (v[0] & 0x0f) | (v[1] << 4)).collect()
+}
+
+pub struct Region {
+    data: Vec<u8>,
+}
+
+impl Region {
+    pub fn new(data: Vec<u8>) -> Result<Self, AnvilError> {
+        if data.len() < HEADER_SIZE {
+            return Err(AnvilError::Truncated);
+        }
+        Ok(Self { data })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AnvilError> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    /// Compressed payload of the chunk at `index`, `None` if the chunk was never generated.
+    fn payload(&self, index: usize) -> Result<Option<(u8, &[u8])>, AnvilError> {
+        let entry = &self.data[index * 4..index * 4 + 4];
+        let offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize * SECTOR_SIZE;
+        if offset == 0 && entry[3] == 0 {
+            return Ok(None);
+        }
+
+        let header = self.data.get(offset..offset + 5).ok_or(AnvilError::BadLocation { index })?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        // The length counts the compression byte.
+        let payload = self.data.get(offset + 5..offset + 4 + len.max(1)).ok_or(AnvilError::BadLocation { index })?;
+        Ok(Some((header[4], payload)))
+    }
+
+    /// `x` and `z` are chunk coordinates, taken modulo the region size.
+    pub fn chunk_nbt(&self, x: i32, z: i32) -> Result<Option<Tag>, AnvilError> {
+        let Some((compression, payload)) = self.payload(chunk_index(x, z))? else {
+            return Ok(None);
+        };
+
+        let mut bytes = vec![];
+        match compression {
+            COMPRESSION_GZIP => GzDecoder::new(payload).read_to_end(&mut bytes)?,
+            COMPRESSION_ZLIB => ZlibDecoder::new(payload).read_to_end(&mut bytes)?,
+            COMPRESSION_NONE => {
+                bytes.extend_from_slice(payload);
+                bytes.len()
+            }
+            c => return Err(AnvilError::UnknownCompression(c)),
+        };
+
+        Ok(Some(crate::nbt::read(&mut bytes.as_slice())?.1))
+    }
+
+    pub fn chunk(&self, x: i32, z: i32) -> Result<Option<(ChunkPosition, ChunkData)>, AnvilError> {
+        self.chunk_nbt(x, z)?.map(|root| read_chunk(&root)).transpose()
+    }
+
+    /// Every generated chunk of the region.
+    pub fn chunks(&self) -> impl Iterator<Item=Result<(ChunkPosition, ChunkData), AnvilError>> + '_ {
+        self.chunks_with(read_chunk)
+    }
+
+    /// Like [`Region::chunks`], taking any id `registry` defines.
+    pub fn chunks_in<'a>(&'a self, registry: &'a BlockRegistry) -> impl Iterator<Item=Result<(ChunkPosition, ChunkData<RegistryState>), AnvilError>> + 'a {
+        self.chunks_with(|root| read_chunk_in(root, registry))
+    }
+
+    fn chunks_with<'a, S: 'a>(&'a self, read: impl Fn(&Tag) -> Result<(ChunkPosition, S), AnvilError> + 'a) -> impl Iterator<Item=Result<(ChunkPosition, S), AnvilError>> + 'a {
+        (0..REGION_SIZE * REGION_SIZE).filter_map(move |i| {
+            let root = self.chunk_nbt(i % REGION_SIZE, i / REGION_SIZE).transpose()?;
+            Some(root.and_then(|root| read(&root)))
+        })
+    }
+}
+
+/// Converts the root compound of an Anvil chunk. Sections that are absent stay air, as do data
+/// values of sections without `Data`.
+pub fn read_chunk(root: &Tag) -> Result<(ChunkPosition, ChunkData), AnvilError> {
+    read_chunk_with(root, |id, data| Block::from_id(id).map(|block| BlockState::new(block, data)))
+}
+
+/// Like [`read_chunk`], taking any id `registry` defines, including the 12-bit ids of `Add`.
+pub fn read_chunk_in(root: &Tag, registry: &BlockRegistry) -> Result<(ChunkPosition, ChunkData<RegistryState>), AnvilError> {
+    read_chunk_with(root, |id, data| registry.state(id, data))
+}
+
+/// `state` maps an id and data value to its block, `None` for unknown ids.
+fn read_chunk_with<S: Copy + Default + Ord>(root: &Tag, state: impl Fn(u16, u8) -> Option<S>) -> Result<(ChunkPosition, ChunkData<S>), AnvilError> {
+    let level = root.get("Level").ok_or(AnvilError::MissingTag("Level"))?;
+    let coordinate = |name| level.get(name).and_then(Tag::as_i64).map(|v| v as i32).ok_or(AnvilError::MissingTag(name));
+    let pos = ChunkPosition::new(coordinate("xPos")?, coordinate("zPos")?);
+
+    let sections: Box<[Section<S>]> = vec![[S::default(); 16 * 16 * 16]; 24].into_boxed_slice();
+    let mut raw: Box<Sections<S>> = sections.try_into().unwrap_or_else(|_| unreachable!());
+
+    let list = level.get("Sections").and_then(Tag::as_list).ok_or(AnvilError::MissingTag("Sections"))?;
+    for section in list {
+        let y = section.get("Y").and_then(Tag::as_i64).ok_or(AnvilError::MissingTag("Y"))?;
+        let blocks = section.get("Blocks").and_then(Tag::as_byte_array).ok_or(AnvilError::MissingTag("Blocks"))?;
+        let add = section.get("Add").and_then(Tag::as_byte_array);
+        let data = section.get("Data").and_then(Tag::as_byte_array);
+        if !(0..24).contains(&y) || blocks.len() != 4096 || [add, data].iter().flatten().any(|a| a.len() != 2048) {
+            return Err(AnvilError::BadSection { y });
+        }
+
+        for (i, block) in raw[y as usize].iter_mut().enumerate() {
+            let id = blocks[i] as u16 | add.map_or(0, |a| (nibble(a, i) as u16) << 8);
+            *block = state(id, data.map_or(0, |d| nibble(d, i))).ok_or_else(|| AnvilError::UnknownBlock {
+                id,
+                position: BlockPosition::new(pos.x * 16 + (i & 0x0f) as i32, y as u32 * 16 + (i >> 8) as u32, pos.z * 16 + ((i >> 4) & 0x0f) as i32),
+            })?;
+        }
+    }
+
+    Ok((pos, ChunkData::from(raw.as_ref())))
+}
+
+impl World {
+    /// Loads every chunk of every `r.{x}.{z}.mca` file in `dir`, one region per rayon task.
+    pub fn load_anvil(dir: impl AsRef<Path>) -> Result<Self, WorldLoadError> {
+        load_regions(dir, |region| region.chunks().collect())
+    }
+}
+
+impl World<RegistryState> {
+    /// Like [`World::load_anvil`], for modded worlds with ids the [`Block`] enum lacks.
+    pub fn load_anvil_in(dir: impl AsRef<Path>, registry: &BlockRegistry) -> Result<Self, WorldLoadError> {
+        load_regions(dir, |region| region.chunks_in(registry).collect())
+    }
+}
+
+type RegionChunks<S> = Result<Vec<(ChunkPosition, ChunkData<S>)>, AnvilError>;
+
+fn load_regions<S: Clone + Ord + Send>(dir: impl AsRef<Path>, chunks: impl Fn(&Region) -> RegionChunks<S> + Sync) -> Result<World<S>, WorldLoadError> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if parse_region_file_name(&path).is_some() {
+            files.push(path);
+        }
+    }
+
+    let region = |path: PathBuf| -> Result<Vec<(ChunkPosition, ChunkData<S>)>, WorldLoadError> {
+        Region::open(&path).and_then(|r| chunks(&r)).map_err(|error| WorldLoadError::Region { path, error })
+    };
+    let regions = files.into_par_iter().map(region).collect::<Result<Vec<_>, _>>()?;
+
+    let mut world = World::new();
+    for (pos, chunk) in regions.into_iter().flatten() {
+        world.insert(pos, chunk);
+    }
+    Ok(world)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Light {
+    /// Zeroed light arrays with `LightPopulated` unset, so the server relights the chunk.
+    Empty,
+    /// Full sky light above the height map and none below it. Block light stays zero.
+    Sky,
+}
+
+/// Builds the root compound of an Anvil chunk. All-air sections are left out like vanilla does.
+pub fn write_chunk(pos: ChunkPosition, chunk: &ChunkData, light: Light) -> Result<Tag, AnvilError> {
+    let raw = chunk.to_raw_sections();
+    if raw[16..].iter().flatten().any(|b| b.block != Block::Air) {
+        return Err(AnvilError::AboveBuildLimit(pos));
+    }
+
+    // One past the highest non-air block of every xz column, indexed x + z * 16.
+    let heights: Vec<usize> = (0..256)
+        .map(|column| (0..256).rev().find(|y| raw[y >> 4][column + ((y & 0x0f) << 8)].block != Block::Air).map_or(0, |y| y + 1))
+        .collect();
+
+    let sections = raw[..16].iter().enumerate()
+        .filter(|(_, blocks)| blocks.iter().any(|b| b.block != Block::Air))
+        .map(|(y, blocks)| {
+            let ids = blocks.iter().map(|b| b.block.id()).collect::<Vec<_>>();
+            let sky = (0..4096).map(|i| match light {
+                Light::Sky if y * 16 + (i >> 8) >= heights[i & 0xff] => 15,
+                _ => 0,
+            });
+
+            let mut section = compound([
+                ("Y", Tag::Byte(y as i8)),
+                ("Blocks", Tag::ByteArray(ids.iter().map(|id| *id as u8).collect())),
+                ("Data", Tag::ByteArray(pack_nibbles(blocks.iter().map(|b| b.data)))),
+                ("BlockLight", Tag::ByteArray(vec![0; 2048])),
+                ("SkyLight", Tag::ByteArray(pack_nibbles(sky))),
+            ]);
+            if ids.iter().any(|id| *id > 0xff) {
+                let add = Tag::ByteArray(pack_nibbles(ids.iter().map(|id| (id >> 8) as u8)));
+                if let Tag::Compound(c) = &mut section {
+                    c.insert("Add".to_string(), add);
+                }
+            }
+            section
+        })
+        .collect();
+
+    let level = compound([
+        ("xPos", Tag::Int(pos.x)),
+        ("zPos", Tag::Int(pos.z)),
+        ("LastUpdate", Tag::Long(0)),
+        ("InhabitedTime", Tag::Long(0)),
+        ("TerrainPopulated", Tag::Byte(1)),
+        ("LightPopulated", Tag::Byte((light == Light::Sky) as i8)),
+        ("V", Tag::Byte(1)),
+        ("HeightMap", Tag::IntArray(heights.iter().map(|h| *h as i32).collect())),
+        ("Sections", Tag::List(sections)),
+        ("Entities", Tag::List(vec![])),
+        ("TileEntities", Tag::List(vec![])),
+    ]);
+    Ok(compound([("Level", level), ("DataVersion", Tag::Int(DATA_VERSION))]))
+}
+
+/// Packs chunk compounds into a region file. Every chunk must belong to the same region.
+pub fn encode_region(chunks: &[(ChunkPosition, Tag)]) -> Result<Vec<u8>, AnvilError> {
+    let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs() as u32);
+
+    let mut out = vec![0; HEADER_SIZE];
+    for (pos, root) in chunks {
+        let payload = crate::nbt::write_zlib("", root);
+        let sectors = (payload.len() + 5).div_ceil(SECTOR_SIZE);
+        if sectors > 0xff {
+            return Err(AnvilError::ChunkTooLarge(*pos));
+        }
+
+        let index = chunk_index(pos.x, pos.z);
+        let location = ((out.len() / SECTOR_SIZE) << 8 | sectors) as u32;
+        out[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+        out[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4].copy_from_slice(&timestamp.to_be_bytes());
+
+        out.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        out.push(COMPRESSION_ZLIB);
+        out.extend_from_slice(&payload);
+        out.resize(out.len().next_multiple_of(SECTOR_SIZE), 0);
+    }
+    Ok(out)
+}
+
+impl World {
+    /// Writes every chunk into `r.{x}.{z}.mca` files in `dir`, one region per rayon task.
+    pub fn save_anvil(&self, dir: impl AsRef<Path>, light: Light) -> Result<(), AnvilError> {
+        let mut regions: HashMap<_, Vec<_>> = HashMap::new();
+        for (pos, chunk) in self.chunks() {
+            regions.entry((pos.x.div_euclid(REGION_SIZE), pos.z.div_euclid(REGION_SIZE))).or_default().push((*pos, chunk));
+        }
+
+        let dir = dir.as_ref();
+        regions.into_par_iter().try_for_each(|((x, z), chunks)| {
+            let chunks = chunks.into_iter()
+                .map(|(pos, chunk)| Ok((pos, write_chunk(pos, chunk, light)?)))
+                .collect::<Result<Vec<_>, AnvilError>>()?;
+            std::fs::write(dir.join(format!("r.{}.{}.mca", x, z)), encode_region(&chunks)?)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A region with one uncompressed chunk at `index`, in the third sector.
+    fn region_with(index: usize, root: &Tag) -> Vec<u8> {
+        let mut payload = vec![];
+        crate::nbt::write(&mut payload, "", root).unwrap();
+        let mut data = vec![0; HEADER_SIZE];
+        data[index * 4..index * 4 + 4].copy_from_slice(&[0, 0, 2, 1]);
+        data.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        data.push(COMPRESSION_NONE);
+        data.extend_from_slice(&payload);
+        data.resize(HEADER_SIZE + SECTOR_SIZE, 0);
+        data
+    }
+
+    #[test]
+    fn reads_hand_built_region() {
+        let root = compound([("Level", compound([("xPos", Tag::Int(33)), ("zPos", Tag::Int(-1))]))]);
+        let region = Region::new(region_with(chunk_index(33, -1), &root)).unwrap();
+        assert_eq!(region.chunk_nbt(33, -1).unwrap(), Some(root));
+        assert_eq!(region.chunk_nbt(0, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let pos = ChunkPosition::new(-3, 5);
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(crate::LocalBlockPosition::new(1, 70, 2), BlockState::new(Block::Stone, 0));
+        chunk.set_block(crate::LocalBlockPosition::new(15, 0, 15), BlockState::new(Block::Wool, 14));
+
+        let region = Region::new(encode_region(&[(pos, write_chunk(pos, &chunk, Light::Sky).unwrap())]).unwrap()).unwrap();
+        let (read_pos, read) = region.chunk(pos.x, pos.z).unwrap().unwrap();
+        assert_eq!(read_pos, pos);
+        assert_eq!(read.to_raw_sections(), chunk.to_raw_sections());
+    }
+
+    #[test]
+    fn region_file_names() {
+        assert_eq!(parse_region_file_name(Path::new("world/region/r.-1.20.mca")), Some((-1, 20)));
+        assert_eq!(parse_region_file_name(Path::new("r.0.mca")), None);
+        assert_eq!(parse_region_file_name(Path::new("r.0.0.mcr")), None);
+    }
+
+    #[test]
+    fn world_round_trip() {
+        let dir = crate::tests::temp_dir("anvil-world");
+        let mut world = World::new();
+        for pos in [ChunkPosition::new(0, 0), ChunkPosition::new(-1, 40)] {
+            let mut chunk = ChunkData::empty();
+            chunk.set_block(crate::LocalBlockPosition::new(5, 64, 6), BlockState::new(Block::Log, 2));
+            world.insert(pos, chunk);
+        }
+        world.save_anvil(&dir, Light::Empty).unwrap();
+        assert!(dir.join("r.0.0.mca").exists() && dir.join("r.-1.1.mca").exists());
+
+        let loaded = World::load_anvil(&dir).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(BlockPosition::new(-16 + 5, 64, 640 + 6)), Some(BlockState::new(Block::Log, 2)));
+
+        let loaded = World::load_anvil_in(&dir, &BlockRegistry::vanilla()).unwrap();
+        assert_eq!(loaded.get(BlockPosition::new(5, 64, 6)), Some(RegistryState::new(Block::Log.id(), 2)));
+
+        std::fs::write(dir.join("r.5.5.mca"), [0; 10]).unwrap();
+        assert!(matches!(World::load_anvil(&dir), Err(WorldLoadError::Region { error: AnvilError::Truncated, .. })));
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert!(matches!(Region::new(vec![0; HEADER_SIZE - 1]), Err(AnvilError::Truncated)));
+
+        let mut data = region_with(7, &compound([]));
+        data.truncate(HEADER_SIZE);
+        let region = Region::new(data).unwrap();
+        assert!(matches!(region.chunk_nbt(7, 0), Err(AnvilError::BadLocation { index: 7 })));
+
+        let mut data = region_with(7, &compound([]));
+        data[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&(SECTOR_SIZE as u32 * 2).to_be_bytes());
+        let region = Region::new(data).unwrap();
+        assert!(matches!(region.chunk_nbt(7, 0), Err(AnvilError::BadLocation { index: 7 })));
+    }
+}
@@ -0,0 +1,561 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use get_size::GetSize;
+
+use crate::flattening::{flat_names, FlatBlockState, NAMESPACE, ParseFlatStateError};
+
+macro_rules! blocks {
+    ($($name:ident = $id:literal => $legacy:literal,)*) => {
+        #[derive(Copy, Clone, Eq, PartialEq, Debug, GetSize, serde::Serialize, serde::Deserialize, PartialOrd, Ord, Hash)]
+        #[repr(u16)]
+        #[allow(clippy::enum_variant_names)]
+        pub enum Block {
+            $($name = $id,)*
+        }
+
+        impl Block {
+            /// Every variant, in id order.
+            pub const ALL: [Block; [$($id),*].len()] = [$(Block::$name,)*];
+
+            /// The 1.12 registry name without namespace, e.g. `planks` for [`Block::Wood`].
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Block::$name => $legacy,)*
+                }
+            }
+        }
+    };
+}
+
+blocks! {
+    Air = 0 => "air",
+    Stone = 1 => "stone",
+    Grass = 2 => "grass",
+    Dirt = 3 => "dirt",
+    Cobblestone = 4 => "cobblestone",
+    Wood = 5 => "planks",
+    Sapling = 6 => "sapling",
+    Bedrock = 7 => "bedrock",
+    WaterSolid = 8 => "flowing_water",
+    Water = 9 => "water",
+    LavaSolid = 10 => "flowing_lava",
+    Lava = 11 => "lava",
+    Sand = 12 => "sand",
+    Gravel = 13 => "gravel",
+    GoldOre = 14 => "gold_ore",
+    IronOre = 15 => "iron_ore",
+    CoalOre = 16 => "coal_ore",
+    Log = 17 => "log",
+    Leaves = 18 => "leaves",
+    Sponge = 19 => "sponge",
+    Glass = 20 => "glass",
+    LapisOre = 21 => "lapis_ore",
+    LapisBlock = 22 => "lapis_block",
+    Dispenser = 23 => "dispenser",
+    Sandstone = 24 => "sandstone",
+    NoteBlock = 25 => "noteblock",
+    BedBlock = 26 => "bed",
+    PoweredRail = 27 => "golden_rail",
+    DetectorRail = 28 => "detector_rail",
+    PistonStickyBase = 29 => "sticky_piston",
+    Web = 30 => "web",
+    LongGrassBottom = 31 => "tallgrass",
+    LongGrassUp = 32 => "deadbush",
+    PistonBase = 33 => "piston",
+    PistonExtension = 34 => "piston_head",
+    Wool = 35 => "wool",
+    PistonMovingPiece = 36 => "piston_extension",
+    YellowFlower = 37 => "yellow_flower",
+    RedRose = 38 => "red_flower",
+    BrownMushroom = 39 => "brown_mushroom",
+    RedMushroom = 40 => "red_mushroom",
+    GoldBlock = 41 => "gold_block",
+    IronBlock = 42 => "iron_block",
+    StepDown = 43 => "double_stone_slab",
+    StepUp = 44 => "stone_slab",
+    Brick = 45 => "brick_block",
+    Tnt = 46 => "tnt",
+    Bookshelf = 47 => "bookshelf",
+    MossyCobblestone = 48 => "mossy_cobblestone",
+    Obsidian = 49 => "obsidian",
+    Torch = 50 => "torch",
+    Fire = 51 => "fire",
+    MobSpawner = 52 => "mob_spawner",
+    WoodStairs = 53 => "oak_stairs",
+    Chest = 54 => "chest",
+    RedstoneWire = 55 => "redstone_wire",
+    DiamondOre = 56 => "diamond_ore",
+    DiamondBlock = 57 => "diamond_block",
+    Workbench = 58 => "crafting_table",
+    Crops = 59 => "wheat",
+    Soil = 60 => "farmland",
+    FurnaceOff = 61 => "furnace",
+    FurnaceOn = 62 => "lit_furnace",
+    SignPost = 63 => "standing_sign",
+    WoodenDoor = 64 => "wooden_door",
+    Ladder = 65 => "ladder",
+    Rails = 66 => "rail",
+    CobblestoneStairs = 67 => "stone_stairs",
+    WallSign = 68 => "wall_sign",
+    Lever = 69 => "lever",
+    StonePlate = 70 => "stone_pressure_plate",
+    IronDoorBlock = 71 => "iron_door",
+    WoodPlate = 72 => "wooden_pressure_plate",
+    RedstoneOreOff = 73 => "redstone_ore",
+    RedstoneOreOn = 74 => "lit_redstone_ore",
+    RedstoneTorchOff = 75 => "unlit_redstone_torch",
+    RedstoneTorchOn = 76 => "redstone_torch",
+    StoneButton = 77 => "stone_button",
+    Snow = 78 => "snow_layer",
+    Ice = 79 => "ice",
+    SnowBlock = 80 => "snow",
+    Cactus = 81 => "cactus",
+    Clay = 82 => "clay",
+    SugarCaneBlock = 83 => "reeds",
+    Jukebox = 84 => "jukebox",
+    Fence = 85 => "fence",
+    Pumpkin = 86 => "pumpkin",
+    Netherrack = 87 => "netherrack",
+    SoulSand = 88 => "soul_sand",
+    Glowstone = 89 => "glowstone",
+    Portal = 90 => "portal",
+    JackOLantern = 91 => "lit_pumpkin",
+    CakeBlock = 92 => "cake",
+    DiodeBlockOff = 93 => "unpowered_repeater",
+    DiodeBlockOn = 94 => "powered_repeater",
+    StainedGlass = 95 => "stained_glass",
+    TrapDoor = 96 => "trapdoor",
+    MonsterEggs = 97 => "monster_egg",
+    SmoothBrick = 98 => "stonebrick",
+    HugeMushroom1 = 99 => "brown_mushroom_block",
+    HugeMushroom2 = 100 => "red_mushroom_block",
+    IronFence = 101 => "iron_bars",
+    ThinGlass = 102 => "glass_pane",
+    MelonBlock = 103 => "melon_block",
+    PumpkinStem = 104 => "pumpkin_stem",
+    MelonStem = 105 => "melon_stem",
+    Vine = 106 => "vine",
+    FenceGate = 107 => "fence_gate",
+    BrickStairs = 108 => "brick_stairs",
+    SmoothStairs = 109 => "stone_brick_stairs",
+    Mycel = 110 => "mycelium",
+    WaterLily = 111 => "waterlily",
+    NetherBrick = 112 => "nether_brick",
+    NetherFence = 113 => "nether_brick_fence",
+    NetherBrickStairs = 114 => "nether_brick_stairs",
+    NetherWarts = 115 => "nether_wart",
+    EnchantmentTable = 116 => "enchanting_table",
+    BrewingStand = 117 => "brewing_stand",
+    Cauldron = 118 => "cauldron",
+    EnderPortal = 119 => "end_portal",
+    EnderPortalFrame = 120 => "end_portal_frame",
+    EnderStone = 121 => "end_stone",
+    DragonEgg = 122 => "dragon_egg",
+    RedstoneLampOff = 123 => "redstone_lamp",
+    RedstoneLampOn = 124 => "lit_redstone_lamp",
+    WoodStepDown = 125 => "double_wooden_slab",
+    WoodStepUp = 126 => "wooden_slab",
+    Cocoa = 127 => "cocoa",
+    SandstoneStairs = 128 => "sandstone_stairs",
+    EmeraldOre = 129 => "emerald_ore",
+    EnderChest = 130 => "ender_chest",
+    TripwireHook = 131 => "tripwire_hook",
+    Tripwire = 132 => "tripwire",
+    EmeraldBlock = 133 => "emerald_block",
+    SpruceWoodStairs = 134 => "spruce_stairs",
+    BirchWoodStairs = 135 => "birch_stairs",
+    JungleWoodStairs = 136 => "jungle_stairs",
+    Command = 137 => "command_block",
+    Beacon = 138 => "beacon",
+    CobbleWall = 139 => "cobblestone_wall",
+    FlowerPot = 140 => "flower_pot",
+    Carrot = 141 => "carrots",
+    Potato = 142 => "potatoes",
+    WoodButton = 143 => "wooden_button",
+    Anvil = 145 => "anvil",
+    TrappedChest = 146 => "trapped_chest",
+    GoldPlate = 147 => "light_weighted_pressure_plate",
+    IronPlate = 148 => "heavy_weighted_pressure_plate",
+    RedstoneComparatorOff = 149 => "unpowered_comparator",
+    RedstoneComparatorOn = 150 => "powered_comparator",
+    DaylightDetectorOff = 151 => "daylight_detector",
+    RedstoneBlock = 152 => "redstone_block",
+    QuartzOre = 153 => "quartz_ore",
+    Hopper = 154 => "hopper",
+    QuartzBlock = 155 => "quartz_block",
+    QuartzStairs = 156 => "quartz_stairs",
+    ActivatorRail = 157 => "activator_rail",
+    Dropper = 158 => "dropper",
+    StainedClay = 159 => "stained_hardened_clay",
+    StainedGlassPane = 160 => "stained_glass_pane",
+    Leaves2 = 161 => "leaves2",
+    Log2 = 162 => "log2",
+    AcaciaStairs = 163 => "acacia_stairs",
+    DarkOakStairs = 164 => "dark_oak_stairs",
+    SlimeBlock = 165 => "slime",
+    Barrier = 166 => "barrier",
+    IronTrapdoor = 167 => "iron_trapdoor",
+    Prismarine = 168 => "prismarine",
+    SeaLantern = 169 => "sea_lantern",
+    HayBlock = 170 => "hay_block",
+    Carpet = 171 => "carpet",
+    HardClay = 172 => "hardened_clay",
+    CoalBlock = 173 => "coal_block",
+    PackedIce = 174 => "packed_ice",
+    DoublePlant = 175 => "double_plant",
+    StandingBanner = 176 => "standing_banner",
+    WallBanner = 177 => "wall_banner",
+    DaylightDetectorOn = 178 => "daylight_detector_inverted",
+    RedSandstone = 179 => "red_sandstone",
+    RedSandstoneStairs = 180 => "red_sandstone_stairs",
+    StoneSlab2 = 181 => "double_stone_slab2",
+    SpruceFenceGate = 183 => "spruce_fence_gate",
+    BirchFenceGate = 184 => "birch_fence_gate",
+    JungleFenceGate = 185 => "jungle_fence_gate",
+    DarkOakFenceGate = 186 => "dark_oak_fence_gate",
+    AcaciaFenceGate = 187 => "acacia_fence_gate",
+    SpruceFence = 188 => "spruce_fence",
+    BirchFence = 189 => "birch_fence",
+    JungleFence = 190 => "jungle_fence",
+    DarkOakFence = 191 => "dark_oak_fence",
+    AcaciaFence = 192 => "acacia_fence",
+    SpruceDoor = 193 => "spruce_door",
+    BirchDoor = 194 => "birch_door",
+    JungleDoor = 195 => "jungle_door",
+    AcaciaDoor = 196 => "acacia_door",
+    DarkOakDoor = 197 => "dark_oak_door",
+    EndRod = 198 => "end_rod",
+    ChorusPlant = 199 => "chorus_plant",
+    ChorusFlower = 200 => "chorus_flower",
+    PurpurBlock = 201 => "purpur_block",
+    PurpurPillar = 202 => "purpur_pillar",
+    PurpurStairs = 203 => "purpur_stairs",
+    PurpurSlabBottom = 204 => "purpur_double_slab",
+    PurpurSlabUp = 205 => "purpur_slab",
+    EndBricks = 206 => "end_bricks",
+    BeetrootBlock = 207 => "beetroots",
+    GrassPath = 208 => "grass_path",
+    EndGateway = 209 => "end_gateway",
+    CommandRepeating = 210 => "repeating_command_block",
+    CommandChain = 211 => "chain_command_block",
+    FrostedIce = 212 => "frosted_ice",
+    Magma = 213 => "magma",
+    NetherWartBlock = 214 => "nether_wart_block",
+    RedNetherBrick = 215 => "red_nether_brick",
+    BoneBlock = 216 => "bone_block",
+    StructureVoid = 217 => "structure_void",
+    Observer = 218 => "observer",
+    WhiteShulkerBox = 219 => "white_shulker_box",
+    OrangeShulkerBox = 220 => "orange_shulker_box",
+    MagentaShulkerBox = 221 => "magenta_shulker_box",
+    LightBlueShulkerBox = 222 => "light_blue_shulker_box",
+    YellowShulkerBox = 223 => "yellow_shulker_box",
+    LimeShulkerBox = 224 => "lime_shulker_box",
+    PinkShulkerBox = 225 => "pink_shulker_box",
+    GrayShulkerBox = 226 => "gray_shulker_box",
+    SilverShulkerBox = 227 => "silver_shulker_box",
+    CyanShulkerBox = 228 => "cyan_shulker_box",
+    PurpleShulkerBox = 229 => "purple_shulker_box",
+    BlueShulkerBox = 230 => "blue_shulker_box",
+    BrownShulkerBox = 231 => "brown_shulker_box",
+    GreenShulkerBox = 232 => "green_shulker_box",
+    RedShulkerBox = 233 => "red_shulker_box",
+    BlackShulkerBox = 234 => "black_shulker_box",
+    WhiteGlazedTerracotta = 235 => "white_glazed_terracotta",
+    OrangeGlazedTerracotta = 236 => "orange_glazed_terracotta",
+    MagentaGlazedTerracotta = 237 => "magenta_glazed_terracotta",
+    LightBlueGlazedTerracotta = 238 => "light_blue_glazed_terracotta",
+    YellowGlazedTerracotta = 239 => "yellow_glazed_terracotta",
+    LimeGlazedTerracotta = 240 => "lime_glazed_terracotta",
+    PinkGlazedTerracotta = 241 => "pink_glazed_terracotta",
+    GrayGlazedTerracotta = 242 => "gray_glazed_terracotta",
+    SilverGlazedTerracotta = 243 => "silver_glazed_terracotta",
+    CyanGlazedTerracotta = 244 => "cyan_glazed_terracotta",
+    PurpleGlazedTerracotta = 245 => "purple_glazed_terracotta",
+    BlueGlazedTerracotta = 246 => "blue_glazed_terracotta",
+    BrownGlazedTerracotta = 247 => "brown_glazed_terracotta",
+    GreenGlazedTerracotta = 248 => "green_glazed_terracotta",
+    RedGlazedTerracotta = 249 => "red_glazed_terracotta",
+    BlackGlazedTerracotta = 250 => "black_glazed_terracotta",
+    Concrete = 251 => "concrete",
+    ConcretePowder = 252 => "concrete_powder",
+    StructureBlock = 255 => "structure_block",
+}
+
+/// Largest id a vanilla 1.12 block can have.
+pub const MAX_BLOCK_ID: u16 = 255;
+
+/// Id -> variant table, `None` for the holes in the id space.
+const BY_ID: [Option<Block>; MAX_BLOCK_ID as usize + 1] = {
+    let mut table = [None; MAX_BLOCK_ID as usize + 1];
+    let mut i = 0;
+    while i < Block::ALL.len() {
+        table[Block::ALL[i] as usize] = Some(Block::ALL[i]);
+        i += 1;
+    }
+    table
+};
+
+impl Block {
+    pub const fn id(self) -> u16 {
+        self as u16
+    }
+
+    /// Returns the variant with the given numeric id, or `None` for ids the enum lacks.
+    pub const fn from_id(id: u16) -> Option<Self> {
+        if id > MAX_BLOCK_ID {
+            return None;
+        }
+        BY_ID[id as usize]
+    }
+
+    /// Inverse of [`Block::name`], ignoring ASCII case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Block::ALL.into_iter().find(|block| block.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl From<Block> for u16 {
+    fn from(block: Block) -> u16 {
+        block.id()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnknownBlockId(pub u16);
+
+impl Display for UnknownBlockId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown block id {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownBlockId {}
+
+impl TryFrom<u16> for Block {
+    type Error = UnknownBlockId;
+
+    fn try_from(id: u16) -> Result<Self, Self::Error> {
+        Block::from_id(id).ok_or(UnknownBlockId(id))
+    }
+}
+
+/// A block with its 4-bit 1.12 data value, which picks e.g. the wool colour, stair facing, log
+/// axis or slab half.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, GetSize, serde::Serialize, serde::Deserialize, PartialOrd, Ord, Hash)]
+pub struct BlockState {
+    pub block: Block,
+    pub data: u8,
+}
+
+impl BlockState {
+    pub const AIR: BlockState = BlockState::new(Block::Air, 0);
+
+    /// Only the low nibble of `data` is kept.
+    pub const fn new(block: Block, data: u8) -> Self {
+        Self { block, data: data & 0x0f }
+    }
+
+    /// `id << 4 | data`, the 1.12 global palette id.
+    pub const fn state_id(self) -> u16 {
+        self.block.id() << 4 | self.data as u16
+    }
+
+    pub const fn from_state_id(id: u16) -> Option<Self> {
+        match Block::from_id(id >> 4) {
+            Some(block) => Some(Self::new(block, (id & 0x0f) as u8)),
+            None => None,
+        }
+    }
+}
+
+impl From<Block> for BlockState {
+    fn from(block: Block) -> Self {
+        Self::new(block, 0)
+    }
+}
+
+impl Default for BlockState {
+    fn default() -> Self {
+        Self::AIR
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseBlockError {
+    UnknownId(UnknownBlockId),
+    /// A numeric id too large for a u16, as typed.
+    IdTooLarge(String),
+    /// The part after `id:` is not a number from 0 to 15.
+    BadData(String),
+    Malformed(ParseFlatStateError),
+    /// `suggestions` are the closest known names, best first, possibly none.
+    UnknownName { name: String, suggestions: Vec<String> },
+}
+
+impl Display for ParseBlockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownId(e) => e.fmt(f),
+            Self::IdTooLarge(id) => write!(f, "unknown block id {}", id),
+            Self::BadData(data) => write!(f, "data value {:?} is not a number from 0 to 15", data),
+            Self::Malformed(e) => e.fmt(f),
+            Self::UnknownName { name, suggestions } if suggestions.is_empty() => write!(f, "unknown block {:?}", name),
+            Self::UnknownName { name, suggestions } => write!(f, "unknown block {:?}, did you mean {}?", name, suggestions.join(" or ")),
+        }
+    }
+}
+
+impl std::error::Error for ParseBlockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnknownId(e) => Some(e),
+            Self::Malformed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(diagonal + (ca != *cb) as usize);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to three known names within a third of `name`'s length in edits, closest first. Legacy and
+/// flattened names are both candidates.
+fn suggestions(name: &str) -> Vec<String> {
+    let name = name.to_ascii_lowercase();
+    let limit = (name.len() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = Block::ALL.iter().map(|block| block.name()).chain(flat_names())
+        .map(|candidate| (edit_distance(&name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    candidates.sort();
+    candidates.dedup_by_key(|(_, candidate)| *candidate);
+    candidates.into_iter().take(3).map(|(_, candidate)| format!("{}:{}", NAMESPACE, candidate)).collect()
+}
+
+/// A data value from 0 to 15.
+fn parse_data(data: &str) -> Result<u8, ParseBlockError> {
+    match data.parse::<u8>() {
+        Ok(data) if data < 16 => Ok(data),
+        _ => Err(ParseBlockError::BadData(data.to_string())),
+    }
+}
+
+impl FromStr for BlockState {
+    type Err = ParseBlockError;
+
+    /// Takes a numeric id like `53`, `id:data` like `35:14`, a 1.12 name like `minecraft:wool` or
+    /// `wool:14`, a flattened state like `minecraft:oak_stairs[facing=north]` or `oak_stairs`, or a
+    /// variant name like `WoodStairs` in any case. Where a flattened name means another block than
+    /// the 1.12 one, such as `grass`, the 1.12 block wins.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let numeric = |id: &str| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+        let block = |id: &str| match id.parse::<u16>() {
+            Ok(id) => Block::try_from(id).map_err(ParseBlockError::UnknownId),
+            Err(_) => Err(ParseBlockError::IdTooLarge(id.to_string())),
+        };
+        if numeric(s) {
+            return block(s).map(BlockState::from);
+        }
+        if let Some((id, data)) = s.split_once(':').filter(|(id, _)| numeric(id)) {
+            return Ok(BlockState::new(block(id)?, parse_data(data)?));
+        }
+
+        let legacy = s.strip_prefix(NAMESPACE).and_then(|s| s.strip_prefix(':')).unwrap_or(s);
+        let (name, data) = legacy.split_once(':').map_or((legacy, None), |(name, data)| (name, Some(data)));
+        if let Some(block) = Block::from_name(name) {
+            return Ok(BlockState::new(block, data.map(parse_data).transpose()?.unwrap_or(0)));
+        }
+
+        let flat = s.parse::<FlatBlockState>().map_err(ParseBlockError::Malformed)?;
+        if let Some(state) = flat.to_legacy() {
+            return Ok(state);
+        }
+        if let Some(block) = Block::ALL.iter().find(|b| format!("{:?}", b).eq_ignore_ascii_case(s)) {
+            return Ok(BlockState::from(*block));
+        }
+        let suggestions = if flat.namespace == NAMESPACE { suggestions(&flat.name) } else { vec![] };
+        Err(ParseBlockError::UnknownName { name: s.to_string(), suggestions })
+    }
+}
+
+impl FromStr for Block {
+    type Err = ParseBlockError;
+
+    /// Any form [`BlockState`] parses, with the data value dropped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<BlockState>().map(|state| state.block)
+    }
+}
+
+impl Display for BlockState {
+    /// The 1.12 name with the data value unless it is 0, e.g. `minecraft:wool:14`. See
+    /// [`flat_name`](crate::flattening::flat_name) for 1.13 and later.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.data {
+            0 => self.block.fmt(f),
+            data => write!(f, "{}:{}", self.block, data),
+        }
+    }
+}
+
+impl Display for Block {
+    /// The 1.12 name, e.g. `minecraft:planks`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", NAMESPACE, self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trip() {
+        for block in Block::ALL {
+            assert_eq!(block.to_string().parse::<Block>(), Ok(block));
+            for data in [1, 15] {
+                let state = BlockState::new(block, data);
+                assert_eq!(state.to_string().parse::<BlockState>(), Ok(state));
+            }
+        }
+        assert_eq!(Block::Wood.to_string(), "minecraft:planks");
+        assert_eq!(BlockState::new(Block::Wool, 14).to_string(), "minecraft:wool:14");
+    }
+
+    #[test]
+    fn parses_every_form() {
+        let red_wool = Ok(BlockState::new(Block::Wool, 14));
+        for s in ["35:14", "minecraft:wool:14", "WOOL:14", "minecraft:red_wool", "red_wool"] {
+            assert_eq!(s.parse::<BlockState>(), red_wool, "{}", s);
+        }
+        assert_eq!("minecraft:log".parse(), Ok(Block::Log));
+        assert_eq!("minecraft:grass".parse(), Ok(Block::Grass));
+        assert_eq!("oak_stairs[facing=north]".parse(), Ok(Block::WoodStairs));
+        assert_eq!("woodstairs".parse(), Ok(Block::WoodStairs));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("99999".parse::<Block>(), Err(ParseBlockError::IdTooLarge("99999".to_string())));
+        assert_eq!("99999".parse::<Block>().unwrap_err().to_string(), "unknown block id 99999");
+        assert_eq!("144".parse::<Block>(), Err(ParseBlockError::UnknownId(UnknownBlockId(144))));
+        assert_eq!("wool:16".parse::<Block>(), Err(ParseBlockError::BadData("16".to_string())));
+        let Err(ParseBlockError::UnknownName { suggestions, .. }) = "minecraft:plank".parse::<Block>() else { panic!() };
+        assert_eq!(suggestions.first().map(String::as_str), Some("minecraft:planks"));
+    }
+}
@@ -0,0 +1,486 @@
+//! Interchangeable chunk encodings, picked per file by a one byte codec id.
+//!
+//! Besides the octree of [`crate::encoding`] there are two flat codecs over the blocks in yzx
+//! order, run-length and one varint per block, and a hybrid that keeps the octree but writes
+//! varint palette indices and optionally run-length encodes `Blocks` leaves. Palettes hold
+//! [`BlockState::state_id`]s.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use bimap::BiBTreeMap;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::{BlockState, ChunkData, leaf_cells, leaf_from_cells, MINIMAL_NODE_SIZE, Node, Nodes, PaletteIndex, RawSection, RawSections};
+use crate::encoding::{check_node_size, DecodeError, TAG_BLOCK, TAG_BLOCKS, TAG_NODES};
+use crate::world::{parse_chunk_file_name, WorldLoadError};
+
+const CHUNK_VOLUME: usize = 16 * 16 * 16 * 24;
+const LEAF_VOLUME: usize = MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE;
+
+/// A [`DecodeError`] of the octree shared by the codecs, or a failure of the codec framing and
+/// varint encodings on top of it.
+#[derive(Debug)]
+pub enum CodecError {
+    Decode(DecodeError),
+    UnknownCodec(u8),
+    /// A varint runs past the end of the input or over 32 bits.
+    BadVarint,
+    /// Runs or indices cover a different number of blocks than the chunk or leaf holds.
+    WrongLength { expected: usize, actual: usize },
+    /// A varint palette entry too large for a state id.
+    StateIdOutOfRange(u32),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "{}", e),
+            Self::UnknownCodec(id) => write!(f, "unknown codec {}", id),
+            Self::BadVarint => f.write_str("malformed varint"),
+            Self::WrongLength { expected, actual } => write!(f, "expected {} blocks, got {}", expected, actual),
+            Self::StateIdOutOfRange(id) => write!(f, "state id {} does not fit in 16 bits", id),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for CodecError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+fn unexpected_eof() -> CodecError {
+    DecodeError::Io(std::io::ErrorKind::UnexpectedEof.into()).into()
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// `None` if the varint runs past the end of `r` or over 32 bits.
+pub(crate) fn read_varint(r: &mut &[u8]) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..32).step_by(7) {
+        let (byte, rest) = r.split_first()?;
+        *r = rest;
+        value |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn varint(r: &mut &[u8]) -> Result<u32, CodecError> {
+    read_varint(r).ok_or(CodecError::BadVarint)
+}
+
+pub trait ChunkCodec: Send + Sync {
+    /// Written in front of the payload by [`encode_framed`], unique among the built-in codecs.
+    fn id(&self) -> u8;
+
+    fn name(&self) -> String;
+
+    fn encode(&self, chunk: &ChunkData) -> Vec<u8>;
+
+    fn decode(&self, bytes: &[u8]) -> Result<ChunkData, CodecError>;
+}
+
+/// [`ChunkData::encode`] as is.
+pub struct OctreeCodec;
+
+/// Runs of `(length, palette index)` over the blocks in yzx order.
+pub struct RleCodec;
+
+/// One varint palette index per block in yzx order.
+pub struct VarintCodec;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LeafEncoding {
+    Varint,
+    Rle,
+}
+
+/// The octree with varint palette indices. Each `leaf` setting has its own id, 3 for varint and 4
+/// for run-length, and is also stored in the payload, so any `HybridCodec` decodes the output of
+/// any other.
+pub struct HybridCodec {
+    pub leaf: LeafEncoding,
+}
+
+pub const OCTREE: OctreeCodec = OctreeCodec;
+pub const RLE: RleCodec = RleCodec;
+pub const VARINT: VarintCodec = VarintCodec;
+pub const HYBRID_VARINT: HybridCodec = HybridCodec { leaf: LeafEncoding::Varint };
+pub const HYBRID_RLE: HybridCodec = HybridCodec { leaf: LeafEncoding::Rle };
+
+pub const BUILTIN: [&dyn ChunkCodec; 5] = [&OCTREE, &RLE, &VARINT, &HYBRID_VARINT, &HYBRID_RLE];
+
+pub fn builtin(id: u8) -> Option<&'static dyn ChunkCodec> {
+    BUILTIN.into_iter().find(|codec| codec.id() == id)
+}
+
+pub fn encode_framed(codec: &dyn ChunkCodec, chunk: &ChunkData) -> Vec<u8> {
+    let mut out = vec![codec.id()];
+    out.extend(codec.encode(chunk));
+    out
+}
+
+/// Decodes the output of [`encode_framed`] with whichever built-in codec wrote it.
+pub fn decode_framed(bytes: &[u8]) -> Result<ChunkData, CodecError> {
+    let (id, payload) = bytes.split_first().ok_or(unexpected_eof())?;
+    builtin(*id).ok_or(CodecError::UnknownCodec(*id))?.decode(payload)
+}
+
+/// Every block in yzx order with its index in a first-seen palette.
+fn flatten(chunk: &ChunkData) -> (Vec<BlockState>, Vec<u32>) {
+    let mut palette = vec![];
+    let mut indices_by_block = HashMap::new();
+    let indices = chunk.to_raw_sections().iter().flatten()
+        .map(|block| *indices_by_block.entry(*block).or_insert_with(|| {
+            palette.push(*block);
+            palette.len() as u32 - 1
+        }))
+        .collect();
+    (palette, indices)
+}
+
+fn write_palette(out: &mut Vec<u8>, palette: &[BlockState]) {
+    write_varint(out, palette.len() as u32);
+    palette.iter().for_each(|b| write_varint(out, b.state_id() as u32));
+}
+
+fn read_state(r: &mut &[u8]) -> Result<BlockState, CodecError> {
+    let id = varint(r)?;
+    let id = u16::try_from(id).map_err(|_| CodecError::StateIdOutOfRange(id))?;
+    Ok(BlockState::from_state_id(id).ok_or(DecodeError::UnknownBlock(id))?)
+}
+
+fn read_palette(r: &mut &[u8]) -> Result<Vec<BlockState>, CodecError> {
+    (0..varint(r)?).map(|_| read_state(r)).collect()
+}
+
+fn unflatten(palette: &[BlockState], indices: impl Iterator<Item=u32>) -> Result<ChunkData, CodecError> {
+    let blocks = indices
+        .map(|i| palette.get(i as usize).copied().ok_or(DecodeError::UnknownIndex(i as usize)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if blocks.len() != CHUNK_VOLUME {
+        return Err(CodecError::WrongLength { expected: CHUNK_VOLUME, actual: blocks.len() });
+    }
+    let sections: Box<[RawSection]> = blocks.chunks_exact(16 * 16 * 16).map(|s| s.try_into().unwrap()).collect();
+    let raw: Box<RawSections> = sections.try_into().unwrap();
+    Ok(ChunkData::from(raw.as_ref()))
+}
+
+fn trailing(r: &[u8]) -> Result<(), CodecError> {
+    if r.is_empty() { Ok(()) } else { Err(DecodeError::TrailingBytes(r.len()).into()) }
+}
+
+fn write_runs(out: &mut Vec<u8>, indices: impl Iterator<Item=u32>) {
+    let mut run: Option<(u32, u32)> = None;
+    for index in indices {
+        run = match run {
+            Some((len, i)) if i == index => Some((len + 1, i)),
+            Some((len, i)) => {
+                write_varint(out, len);
+                write_varint(out, i);
+                Some((1, index))
+            }
+            None => Some((1, index)),
+        };
+    }
+    if let Some((len, i)) = run {
+        write_varint(out, len);
+        write_varint(out, i);
+    }
+}
+
+/// Reads runs until they cover exactly `count` entries.
+fn read_runs(r: &mut &[u8], count: usize) -> Result<Vec<u32>, CodecError> {
+    let mut indices = Vec::with_capacity(count);
+    while indices.len() < count {
+        let (len, index) = (varint(r)? as usize, varint(r)?);
+        if len == 0 || indices.len() + len > count {
+            return Err(CodecError::WrongLength { expected: count, actual: indices.len() + len });
+        }
+        indices.extend(std::iter::repeat_n(index, len));
+    }
+    Ok(indices)
+}
+
+impl ChunkCodec for OctreeCodec {
+    fn id(&self) -> u8 {
+        0
+    }
+
+    fn name(&self) -> String {
+        "octree".to_string()
+    }
+
+    fn encode(&self, chunk: &ChunkData) -> Vec<u8> {
+        chunk.encode()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ChunkData, CodecError> {
+        Ok(ChunkData::decode(bytes)?)
+    }
+}
+
+impl ChunkCodec for RleCodec {
+    fn id(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> String {
+        "rle".to_string()
+    }
+
+    fn encode(&self, chunk: &ChunkData) -> Vec<u8> {
+        let (palette, indices) = flatten(chunk);
+        let mut out = vec![];
+        write_palette(&mut out, &palette);
+        write_runs(&mut out, indices.into_iter());
+        out
+    }
+
+    fn decode(&self, mut bytes: &[u8]) -> Result<ChunkData, CodecError> {
+        let r = &mut bytes;
+        let palette = read_palette(r)?;
+        let indices = read_runs(r, CHUNK_VOLUME)?;
+        trailing(r)?;
+        unflatten(&palette, indices.into_iter())
+    }
+}
+
+impl ChunkCodec for VarintCodec {
+    fn id(&self) -> u8 {
+        2
+    }
+
+    fn name(&self) -> String {
+        "varint".to_string()
+    }
+
+    fn encode(&self, chunk: &ChunkData) -> Vec<u8> {
+        let (palette, indices) = flatten(chunk);
+        let mut out = vec![];
+        write_palette(&mut out, &palette);
+        indices.into_iter().for_each(|i| write_varint(&mut out, i));
+        out
+    }
+
+    fn decode(&self, mut bytes: &[u8]) -> Result<ChunkData, CodecError> {
+        let r = &mut bytes;
+        let palette = read_palette(r)?;
+        let indices = (0..CHUNK_VOLUME).map(|_| varint(r)).collect::<Result<Vec<_>, _>>()?;
+        trailing(r)?;
+        unflatten(&palette, indices.into_iter())
+    }
+}
+
+type DecodedNodes<T> = (Box<[Node<T>; 24]>, BiBTreeMap<T, BlockState>);
+
+impl HybridCodec {
+    fn encode_node<T: PaletteIndex>(&self, node: &Node<T>, out: &mut Vec<u8>) {
+        match node {
+            Node::Block(id) => {
+                out.push(TAG_BLOCK);
+                write_varint(out, (*id).into() as u32);
+            }
+            Node::Blocks(blocks) => {
+                out.push(TAG_BLOCKS);
+                let indices = leaf_cells(blocks).iter().map(|id| (*id).into() as u32);
+                match self.leaf {
+                    LeafEncoding::Varint => indices.for_each(|i| write_varint(out, i)),
+                    LeafEncoding::Rle => write_runs(out, indices),
+                }
+            }
+            Node::Nodes(children) => {
+                out.push(TAG_NODES);
+                children.iter().for_each(|child| self.encode_node(child, out));
+            }
+        }
+    }
+
+    fn encode_nodes<T: PaletteIndex>(&self, nodes: &[Node<T>; 24], blocks: &BiBTreeMap<T, BlockState>, out: &mut Vec<u8>) {
+        write_varint(out, blocks.len() as u32);
+        for (index, block) in blocks {
+            write_varint(out, (*index).into() as u32);
+            write_varint(out, block.state_id() as u32);
+        }
+        nodes.iter().for_each(|node| self.encode_node(node, out));
+    }
+}
+
+/// `size` is the edge length of the node, 16 for a section.
+fn decode_hybrid_node<T: PaletteIndex + TryFrom<u32>>(r: &mut &[u8], leaf: LeafEncoding, blocks: &BiBTreeMap<T, BlockState>, size: usize) -> Result<Node<T>, CodecError> {
+    let index = |i: u32| match T::try_from(i) {
+        Ok(index) if blocks.contains_left(&index) => Ok(index),
+        _ => Err(CodecError::Decode(DecodeError::UnknownIndex(i as usize))),
+    };
+
+    if let Some(tag) = r.first() {
+        check_node_size(*tag, size)?;
+    }
+    match r.split_first().map(|(tag, rest)| (*tag, rest)) {
+        Some((TAG_BLOCK, rest)) => {
+            *r = rest;
+            Ok(Node::Block(index(varint(r)?)?))
+        }
+        Some((TAG_BLOCKS, rest)) => {
+            *r = rest;
+            let indices = match leaf {
+                LeafEncoding::Varint => (0..LEAF_VOLUME).map(|_| varint(r)).collect::<Result<Vec<_>, _>>()?,
+                LeafEncoding::Rle => read_runs(r, LEAF_VOLUME)?,
+            };
+            let leaf = indices.into_iter().map(index).collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::Blocks(leaf_from_cells(&leaf).unwrap()))
+        }
+        Some((TAG_NODES, rest)) => {
+            *r = rest;
+            let children = (0..8).map(|_| decode_hybrid_node(r, leaf, blocks, size / 2)).collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::Nodes(children.try_into().unwrap()))
+        }
+        Some((tag, _)) => Err(DecodeError::UnknownTag(tag).into()),
+        None => Err(unexpected_eof()),
+    }
+}
+
+fn decode_hybrid_nodes<T: PaletteIndex + TryFrom<u32>>(r: &mut &[u8], leaf: LeafEncoding, palette: &[(u32, BlockState)]) -> Result<DecodedNodes<T>, CodecError> {
+    let mut blocks = BiBTreeMap::new();
+    for (index, block) in palette {
+        let index = T::try_from(*index).map_err(|_| DecodeError::UnknownIndex(*index as usize))?;
+        if blocks.insert_no_overwrite(index, *block).is_err() {
+            return Err(DecodeError::DuplicatePaletteEntry(index.into()).into());
+        }
+    }
+    let nodes = (0..24).map(|_| decode_hybrid_node(r, leaf, &blocks, 16)).collect::<Result<Box<[_]>, _>>()?;
+    Ok((nodes.try_into().unwrap(), blocks))
+}
+
+impl ChunkCodec for HybridCodec {
+    fn id(&self) -> u8 {
+        3 + self.leaf as u8
+    }
+
+    fn name(&self) -> String {
+        format!("hybrid ({:?} leaves)", self.leaf).to_lowercase()
+    }
+
+    fn encode(&self, chunk: &ChunkData) -> Vec<u8> {
+        let mut out = vec![self.leaf as u8];
+        match &chunk.nodes {
+            Nodes::Large(nodes, blocks) => self.encode_nodes(nodes, blocks, &mut out),
+            Nodes::Small(nodes, blocks) => self.encode_nodes(nodes, blocks, &mut out),
+        }
+        out
+    }
+
+    fn decode(&self, mut bytes: &[u8]) -> Result<ChunkData, CodecError> {
+        let r = &mut bytes;
+        let leaf = match r.split_first() {
+            Some((0, rest)) => {
+                *r = rest;
+                LeafEncoding::Varint
+            }
+            Some((1, rest)) => {
+                *r = rest;
+                LeafEncoding::Rle
+            }
+            Some((tag, _)) => return Err(DecodeError::UnknownTag(*tag).into()),
+            None => return Err(unexpected_eof()),
+        };
+
+        let palette = (0..varint(r)?)
+            .map(|_| Ok((varint(r)?, read_state(r)?)))
+            .collect::<Result<Vec<_>, CodecError>>()?;
+
+        let nodes = if palette.iter().all(|(index, _)| *index <= u8::MAX as u32) {
+            let (nodes, blocks) = decode_hybrid_nodes(r, leaf, &palette)?;
+            Nodes::Small(nodes, blocks)
+        } else {
+            let (nodes, blocks) = decode_hybrid_nodes(r, leaf, &palette)?;
+            Nodes::Large(nodes, blocks)
+        };
+        trailing(r)?;
+        Ok(ChunkData { nodes })
+    }
+}
+
+/// Total encoded size of every `{x}_{z}.dat` chunk in `dir` under each built-in codec, plus the
+/// raw size of the files themselves first.
+pub fn report(dir: impl AsRef<Path>) -> Result<Vec<(String, u64)>, WorldLoadError> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if parse_chunk_file_name(&path).is_some() {
+            files.push(path);
+        }
+    }
+
+    let sizes = files.into_par_iter()
+        .map(|path| {
+            let raw = std::fs::metadata(&path)?.len();
+            let chunk = ChunkData::read_dat(&path).map_err(|error| WorldLoadError::Chunk { path, error })?;
+            Ok(std::iter::once(raw).chain(BUILTIN.iter().map(|codec| codec.encode(&chunk).len() as u64)).collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>, WorldLoadError>>()?;
+
+    let names = std::iter::once("raw .dat".to_string()).chain(BUILTIN.iter().map(|codec| codec.name()));
+    Ok(names.enumerate().map(|(i, name)| (name, sizes.iter().map(|s| s[i]).sum())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, LocalBlockPosition};
+
+    fn chunk() -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        chunk.fill(LocalBlockPosition::new(0, 0, 0), LocalBlockPosition::new(15, 63, 15), Block::Stone.into());
+        chunk.set_block(LocalBlockPosition::new(3, 64, 5), BlockState::new(Block::Wool, 14));
+        chunk
+    }
+
+    #[test]
+    fn builtin_round_trip() {
+        let chunk = chunk();
+        for codec in BUILTIN {
+            let decoded = decode_framed(&encode_framed(codec, &chunk)).unwrap();
+            assert!(decoded.to_raw_sections() == chunk.to_raw_sections(), "{}", codec.name());
+        }
+    }
+
+    #[test]
+    fn hybrid_rejects_deep_nodes() {
+        let mut bytes = vec![LeafEncoding::Varint as u8, 1, 0, 0];
+        bytes.extend(vec![TAG_NODES; 1 << 20]);
+        assert!(matches!(HYBRID_VARINT.decode(&bytes), Err(CodecError::Decode(DecodeError::MisplacedNode { tag: TAG_NODES, size: 4 }))));
+    }
+
+    #[test]
+    fn rejects_state_ids_over_16_bits() {
+        let mut bytes = vec![LeafEncoding::Varint as u8, 1, 0];
+        write_varint(&mut bytes, 0x1_0000);
+        assert!(matches!(HYBRID_VARINT.decode(&bytes), Err(CodecError::StateIdOutOfRange(0x1_0000))));
+
+        let mut bytes = vec![1];
+        write_varint(&mut bytes, 0x1_0001);
+        assert!(matches!(RLE.decode(&bytes), Err(CodecError::StateIdOutOfRange(0x1_0001))));
+    }
+}
@@ -0,0 +1,361 @@
+//! A sparse voxel DAG: the sections of a whole [`World`] as one hash-consed node store, so every
+//! distinct subtree is kept once no matter how many sections or chunks contain it.
+//!
+//! Palette indices only mean something within a chunk, so the DAG maps every chunk onto a single
+//! world palette first. Nodes refer to each other by [`NodeId`], and uniform nodes are stored in
+//! the id itself rather than in the store.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::mem::size_of;
+
+use bimap::BiBTreeMap;
+use get_size::GetSize;
+use hashbrown::hash_table::{Entry, HashTable};
+
+use crate::{BlockPosition, BlockState, ChunkData, ChunkPosition, Leaf, leaf_cells, LocalBlockPosition, MINIMAL_NODE_SIZE, Node, Nodes, PaletteIndex, Section, Sections};
+use crate::world::{World, WORLD_HEIGHT};
+
+const KIND_SHIFT: u32 = 30;
+const KIND_BLOCK: u32 = 0;
+const KIND_NODES: u32 = 1;
+const KIND_SMALL_LEAF: u32 = 2;
+const KIND_LARGE_LEAF: u32 = 3;
+
+/// The kind of node in the top two bits and its world palette index or position in the store in
+/// the rest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    fn new(kind: u32, index: usize) -> Self {
+        assert!(index < 1 << KIND_SHIFT, "at most 2^30 nodes of a kind");
+        Self(kind << KIND_SHIFT | index as u32)
+    }
+
+    fn kind(self) -> u32 {
+        self.0 >> KIND_SHIFT
+    }
+
+    fn index(self) -> usize {
+        (self.0 & ((1 << KIND_SHIFT) - 1)) as usize
+    }
+}
+
+/// Deduplicated values of one kind, in insertion order. The hash table only holds positions in
+/// `values`, so every value is stored once.
+#[derive(Debug)]
+struct Interned<V> {
+    values: Vec<V>,
+    ids: HashTable<u32>,
+    hasher: RandomState,
+}
+
+impl<V> Default for Interned<V> {
+    fn default() -> Self {
+        Self { values: vec![], ids: HashTable::new(), hasher: RandomState::new() }
+    }
+}
+
+impl<V: Eq + Hash> Interned<V> {
+    fn intern(&mut self, value: V) -> usize {
+        let Self { values, ids, hasher } = self;
+        let entry = ids.entry(
+            hasher.hash_one(&value),
+            |index| values[*index as usize] == value,
+            |index| hasher.hash_one(&values[*index as usize]),
+        );
+        match entry {
+            Entry::Occupied(entry) => *entry.get() as usize,
+            Entry::Vacant(entry) => {
+                entry.insert(values.len() as u32);
+                values.push(value);
+                values.len() - 1
+            }
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        let Self { values, ids, hasher } = self;
+        values.shrink_to_fit();
+        ids.shrink_to_fit(|index| hasher.hash_one(&values[*index as usize]));
+    }
+
+    fn bytes(&self) -> usize {
+        self.values.capacity() * size_of::<V>()
+    }
+
+    fn index_bytes(&self) -> usize {
+        self.ids.allocation_size()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DagStats {
+    /// Sections in the DAG, including empty ones.
+    pub sections: usize,
+    /// Inner nodes and leaves of the sections as separate trees. Uniform nodes live in their
+    /// [`NodeId`] and are not counted on either side.
+    pub tree_nodes: usize,
+    pub unique_nodes: usize,
+    /// [`GetSize`] of the separate trees, palettes left out.
+    pub tree_bytes: usize,
+    /// The store, the section roots and the hash tables, palettes left out.
+    pub dag_bytes: usize,
+    /// The part of `dag_bytes` taken by the hash tables used to find existing nodes.
+    pub index_bytes: usize,
+}
+
+/// `S` is the palette state, see [`World`].
+#[derive(Debug)]
+pub struct WorldDag<S: Ord = BlockState> {
+    nodes: Interned<[NodeId; 8]>,
+    /// Leaves whose world palette indices all fit in a byte.
+    small_leaves: Interned<Leaf<u8, MINIMAL_NODE_SIZE>>,
+    large_leaves: Interned<Leaf<u16, MINIMAL_NODE_SIZE>>,
+    palette: BiBTreeMap<u16, S>,
+    chunks: HashMap<ChunkPosition, DagChunk>,
+    tree_nodes: usize,
+    tree_bytes: usize,
+}
+
+/// The section roots of a chunk, with what its sections took as separate trees so the totals can
+/// drop them again when the chunk is replaced.
+#[derive(Debug)]
+struct DagChunk {
+    roots: [NodeId; 24],
+    tree_nodes: usize,
+    tree_bytes: usize,
+}
+
+impl<S: Ord> Default for WorldDag<S> {
+    fn default() -> Self {
+        Self {
+            nodes: Interned::default(),
+            small_leaves: Interned::default(),
+            large_leaves: Interned::default(),
+            palette: BiBTreeMap::new(),
+            chunks: HashMap::new(),
+            tree_nodes: 0,
+            tree_bytes: 0,
+        }
+    }
+}
+
+impl<S: Clone + Ord> WorldDag<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_world(world: &World<S>) -> Self {
+        let mut dag = Self::new();
+        for (pos, chunk) in world.chunks() {
+            dag.insert(*pos, chunk);
+        }
+        dag
+    }
+
+    /// Adds `chunk`, replacing the one at `pos`. Nodes only the old chunk used stay in the store.
+    ///
+    /// # Panics
+    /// If the world palette would exceed 65536 states.
+    pub fn insert(&mut self, pos: ChunkPosition, chunk: &ChunkData<S>) {
+        let chunk = match &chunk.nodes {
+            Nodes::Large(nodes, blocks) => self.insert_sections(nodes, blocks),
+            Nodes::Small(nodes, blocks) => self.insert_sections(nodes, blocks),
+        };
+        self.tree_nodes += chunk.tree_nodes;
+        self.tree_bytes += chunk.tree_bytes;
+        if let Some(old) = self.chunks.insert(pos, chunk) {
+            self.tree_nodes -= old.tree_nodes;
+            self.tree_bytes -= old.tree_bytes;
+        }
+    }
+
+    fn insert_sections<T: PaletteIndex>(&mut self, nodes: &[Node<T>; 24], blocks: &BiBTreeMap<T, S>) -> DagChunk {
+        let mut remap = vec![0; blocks.left_values().next_back().map_or(0, |i| (*i).into() + 1)];
+        for (index, block) in blocks {
+            remap[(*index).into()] = self.palette_index(block);
+        }
+        DagChunk {
+            roots: nodes.each_ref().map(|node| self.intern_tree(node, &remap)),
+            tree_nodes: nodes.iter().map(count_nodes).sum(),
+            tree_bytes: nodes.iter().map(Node::get_size).sum(),
+        }
+    }
+
+    fn palette_index(&mut self, block: &S) -> u16 {
+        if let Some(index) = self.palette.get_by_right(block) {
+            return *index;
+        }
+        let index = u16::try_from(self.palette.len()).expect("world palette has at most 65536 states");
+        self.palette.insert(index, block.clone());
+        index
+    }
+
+    fn intern_tree<T: PaletteIndex>(&mut self, node: &Node<T>, remap: &[u16]) -> NodeId {
+        match node {
+            Node::Block(id) => NodeId::new(KIND_BLOCK, remap[(*id).into()] as usize),
+            Node::Blocks(blocks) => {
+                let leaf = blocks.map(|plane| plane.map(|row| row.map(|id| remap[id.into()])));
+                if leaf_cells(&leaf).iter().all(|index| *index <= u8::MAX as u16) {
+                    NodeId::new(KIND_SMALL_LEAF, self.small_leaves.intern(leaf.map(|plane| plane.map(|row| row.map(|index| index as u8)))))
+                } else {
+                    NodeId::new(KIND_LARGE_LEAF, self.large_leaves.intern(leaf))
+                }
+            }
+            Node::Nodes(children) => {
+                let children = children.each_ref().map(|child| self.intern_tree(child, remap));
+                NodeId::new(KIND_NODES, self.nodes.intern(children))
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns `None` if the chunk is not in the DAG or `pos` is above the world.
+    #[allow(dead_code)]
+    pub fn get(&self, pos: BlockPosition) -> Option<S> {
+        if pos.y as u32 >= WORLD_HEIGHT {
+            return None;
+        }
+        let roots = &self.chunks.get(&pos.into())?.roots;
+        let pos = LocalBlockPosition::from(pos);
+        let (x, y, z) = (((pos.c >> 8) & 0x0f) as usize, (pos.c >> 16) as usize, (pos.c & 0x0f) as usize);
+
+        let (mut id, mut size, (mut x, mut y, mut z)) = (roots[y >> 4], 16, (x, y & 0x0f, z));
+        let index = loop {
+            match id.kind() {
+                KIND_BLOCK => break id.index() as u16,
+                KIND_SMALL_LEAF => break self.small_leaves.values[id.index()][y][z][x] as u16,
+                KIND_LARGE_LEAF => break self.large_leaves.values[id.index()][y][z][x],
+                _ => {
+                    size /= 2;
+                    id = self.nodes.values[id.index()][x / size + ((y / size) << 2) + ((z / size) << 1)];
+                    (x, y, z) = (x % size, y % size, z % size);
+                }
+            }
+        };
+        self.palette.get_by_left(&index).cloned()
+    }
+
+    /// Expands the chunk at `pos` back into a [`ChunkData`] with a palette of its own.
+    #[allow(dead_code)]
+    pub fn chunk(&self, pos: ChunkPosition) -> Option<ChunkData<S>> {
+        let roots = &self.chunks.get(&pos)?.roots;
+        let sections: Box<[Section<S>]> = roots.iter()
+            .map(|root| {
+                let mut section = [0; 16 * 16 * 16];
+                self.expand(*root, &mut section, 0, 0, 0, 16);
+                section.map(|index| self.palette.get_by_left(&index).unwrap().clone())
+            })
+            .collect();
+        let sections: Box<Sections<S>> = sections.try_into().unwrap_or_else(|_| unreachable!());
+        Some(ChunkData::from(sections.as_ref()))
+    }
+
+    fn expand(&self, id: NodeId, section: &mut Section<u16>, x: usize, y: usize, z: usize, size: usize) {
+        match id.kind() {
+            KIND_BLOCK => for y in y..y + size {
+                for z in z..z + size {
+                    section[x + (z << 4) + (y << 8)..(size + x) + (z << 4) + (y << 8)].fill(id.index() as u16);
+                }
+            },
+            KIND_SMALL_LEAF => copy_leaf(&self.small_leaves.values[id.index()], section, x, y, z),
+            KIND_LARGE_LEAF => copy_leaf(&self.large_leaves.values[id.index()], section, x, y, z),
+            _ => {
+                let size = size / 2;
+                for (i, child) in self.nodes.values[id.index()].iter().enumerate() {
+                    self.expand(*child, section, x + (i & 1) * size, y + (i >> 2) * size, z + ((i >> 1) & 1) * size, size);
+                }
+            }
+        }
+    }
+
+    /// Releases the spare capacity of the store and its hash tables.
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.small_leaves.shrink_to_fit();
+        self.large_leaves.shrink_to_fit();
+        self.chunks.shrink_to_fit();
+    }
+
+    pub fn stats(&self) -> DagStats {
+        let index_bytes = self.nodes.index_bytes() + self.small_leaves.index_bytes() + self.large_leaves.index_bytes();
+        DagStats {
+            sections: self.chunks.len() * 24,
+            tree_nodes: self.tree_nodes,
+            unique_nodes: self.nodes.values.len() + self.small_leaves.values.len() + self.large_leaves.values.len(),
+            tree_bytes: self.tree_bytes,
+            dag_bytes: self.nodes.bytes() + self.small_leaves.bytes() + self.large_leaves.bytes()
+                + self.chunks.capacity() * size_of::<(ChunkPosition, DagChunk)>() + index_bytes,
+            index_bytes,
+        }
+    }
+}
+
+fn copy_leaf<V: Copy + Into<u16>>(leaf: &Leaf<V, MINIMAL_NODE_SIZE>, section: &mut Section<u16>, x: usize, y: usize, z: usize) {
+    for (i, row) in leaf.as_flattened().iter().enumerate() {
+        let (z, y) = (z + i % MINIMAL_NODE_SIZE, y + i / MINIMAL_NODE_SIZE);
+        section[x + (z << 4) + (y << 8)..MINIMAL_NODE_SIZE + x + (z << 4) + (y << 8)].copy_from_slice(&row.map(Into::into));
+    }
+}
+
+/// Inner nodes and leaves, the nodes the store keeps.
+fn count_nodes<T: PaletteIndex>(node: &Node<T>) -> usize {
+    match node {
+        Node::Block(_) => 0,
+        Node::Blocks(_) => 1,
+        Node::Nodes(children) => 1 + children.iter().map(count_nodes).sum::<usize>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    fn stone_chunk() -> ChunkData {
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(LocalBlockPosition::new(1, 2, 3), BlockState::new(Block::Stone, 0));
+        chunk.set_block(LocalBlockPosition::new(9, 40, 15), BlockState::new(Block::Dirt, 0));
+        chunk
+    }
+
+    #[test]
+    fn expands_inserted_chunks() {
+        let mut dag = WorldDag::new();
+        assert!(dag.is_empty());
+        dag.insert(ChunkPosition::new(0, 0), &stone_chunk());
+        dag.insert(ChunkPosition::new(1, 0), &stone_chunk());
+        assert_eq!(dag.len(), 2);
+
+        let chunk = dag.chunk(ChunkPosition::new(1, 0)).unwrap();
+        assert_eq!(chunk.to_raw_sections(), stone_chunk().to_raw_sections());
+        assert_eq!(dag.get(BlockPosition::new(16 + 9, 40, 15)), Some(BlockState::new(Block::Dirt, 0)));
+        assert_eq!(dag.stats().tree_nodes, 2 * dag.stats().unique_nodes);
+    }
+
+    #[test]
+    fn replacing_a_chunk_replaces_its_counts() {
+        let stats = |chunks: &[ChunkData]| {
+            let mut dag = WorldDag::new();
+            for chunk in chunks {
+                dag.insert(ChunkPosition::new(0, 0), chunk);
+            }
+            let stats = dag.stats();
+            (stats.tree_nodes, stats.tree_bytes)
+        };
+        assert_eq!(stats(&[stone_chunk(), ChunkData::empty()]), stats(&[ChunkData::empty()]));
+        assert_eq!(stats(&[ChunkData::empty(), stone_chunk()]), stats(&[stone_chunk()]));
+        assert_ne!(stats(&[stone_chunk()]).0, 0);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::{Block, BlockPosition, BlockState, ChunkData, RawSections, Section, Sections};
+use crate::registry::{BlockRegistry, RegistryState};
+
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+const CHUNK_VOLUME: usize = SECTION_VOLUME * 24;
+
+/// Encoding of the block ids in a `.dat` dump: 24 sections of 4096 ids each, in yzx order. Dumps
+/// carry no data values, blocks read from them have data 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatFormat {
+    /// Big-endian u16 per block, as in `data/`.
+    U16,
+    /// A single byte per block, as in `data_8/`.
+    U8,
+}
+
+impl DatFormat {
+    pub fn len(self) -> usize {
+        match self {
+            Self::U16 => CHUNK_VOLUME * 2,
+            Self::U8 => CHUNK_VOLUME,
+        }
+    }
+
+    fn detect(len: usize) -> Result<Self, ChunkLoadError> {
+        match len {
+            l if l == Self::U16.len() => Ok(Self::U16),
+            l if l == Self::U8.len() => Ok(Self::U8),
+            l if l < Self::U16.len() => Err(ChunkLoadError::Truncated { len }),
+            _ => Err(ChunkLoadError::WrongLength { expected: Self::U16.len(), actual: len }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ChunkLoadError {
+    Io(std::io::Error),
+    /// The file is shorter than 24 sections of u16 ids and is not 24 sections of u8 ids either.
+    Truncated { len: usize },
+    /// The file is longer than 24 sections in either format.
+    WrongLength { expected: usize, actual: usize },
+    /// `index` is the offset in blocks from the start of the file.
+    UnknownBlock { id: u16, index: usize, position: BlockPosition },
+}
+
+impl Display for ChunkLoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Truncated { len } => write!(f, "file is truncated: {} bytes is neither {} bytes of u8 ids nor {} bytes of u16 ids", len, DatFormat::U8.len(), DatFormat::U16.len()),
+            Self::WrongLength { expected, actual } => write!(f, "expected {} bytes, got {}", expected, actual),
+            Self::UnknownBlock { id, index, position } => write!(f, "unknown block id {} at index {} ({:?})", id, index, position),
+        }
+    }
+}
+
+impl std::error::Error for ChunkLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ChunkLoadError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Chunk-relative position of the `index`-th block of a `.dat` dump.
+fn position(index: usize) -> BlockPosition {
+    let (section, i) = (index / SECTION_VOLUME, index % SECTION_VOLUME);
+    BlockPosition::new((i & 0x0f) as i32, (section * 16 + (i >> 8)) as u32, ((i >> 4) & 0x0f) as i32)
+}
+
+/// Validates every id of a `.dat` dump and splits it into sections. The format is picked by length.
+pub fn parse_sections(bytes: &[u8]) -> Result<Box<RawSections>, ChunkLoadError> {
+    parse_sections_with(bytes, |id| Block::from_id(id).map(BlockState::from))
+}
+
+/// Like [`parse_sections`], but takes any id `registry` defines.
+#[allow(dead_code)]
+pub fn parse_sections_in(bytes: &[u8], registry: &BlockRegistry) -> Result<Box<Sections<RegistryState>>, ChunkLoadError> {
+    parse_sections_with(bytes, |id| registry.state(id, 0))
+}
+
+/// `state` maps an id to its block, `None` for unknown ids.
+fn parse_sections_with<S: Copy>(bytes: &[u8], state: impl Fn(u16) -> Option<S>) -> Result<Box<Sections<S>>, ChunkLoadError> {
+    let ids: Box<dyn Iterator<Item=u16>> = match DatFormat::detect(bytes.len())? {
+        DatFormat::U16 => Box::new(bytes.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]]))),
+        DatFormat::U8 => Box::new(bytes.iter().map(|b| *b as u16)),
+    };
+
+    let blocks = ids.enumerate()
+        .map(|(index, id)| state(id).ok_or(ChunkLoadError::UnknownBlock { id, index, position: position(index) }))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let sections: Box<[Section<S>]> = blocks.chunks_exact(SECTION_VOLUME).map(|s| s.try_into().unwrap()).collect();
+    Ok(sections.try_into().unwrap_or_else(|_| unreachable!()))
+}
+
+pub fn read_sections(path: impl AsRef<Path>) -> Result<Box<RawSections>, ChunkLoadError> {
+    parse_sections(&std::fs::read(path)?)
+}
+
+/// Inverse of [`parse_sections`]: serializes the sections in the given format, dropping data values.
+pub fn encode_sections(sections: &RawSections, format: DatFormat) -> Vec<u8> {
+    let blocks = sections.iter().flatten().map(|b| b.block.id());
+    match format {
+        DatFormat::U16 => blocks.flat_map(u16::to_be_bytes).collect(),
+        // Every vanilla id is at most 255, so a byte always holds it.
+        DatFormat::U8 => blocks.map(|id| id as u8).collect(),
+    }
+}
+
+pub fn write_sections(path: impl AsRef<Path>, sections: &RawSections, format: DatFormat) -> std::io::Result<()> {
+    std::fs::write(path, encode_sections(sections, format))
+}
+
+impl ChunkData {
+    pub fn read_dat(path: impl AsRef<Path>) -> Result<Self, ChunkLoadError> {
+        Ok(ChunkData::from(read_sections(path)?.as_ref()))
+    }
+
+    pub fn write_dat(&self, path: impl AsRef<Path>, format: DatFormat) -> std::io::Result<()> {
+        write_sections(path, &self.to_raw_sections(), format)
+    }
+}
+
+impl ChunkData<RegistryState> {
+    /// Reads a `.dat` dump whose ids are defined by `registry` rather than [`Block`].
+    #[allow(dead_code)]
+    pub fn read_dat_in(path: impl AsRef<Path>, registry: &BlockRegistry) -> Result<Self, ChunkLoadError> {
+        Ok(ChunkData::from(parse_sections_in(&std::fs::read(path)?, registry)?.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::BlockDefinition;
+
+    fn sections() -> Box<RawSections> {
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(crate::LocalBlockPosition::new(4, 100, 9), BlockState::from(Block::Stone));
+        chunk.set_block(crate::LocalBlockPosition::new(0, 0, 0), BlockState::from(Block::Bedrock));
+        chunk.to_raw_sections()
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [DatFormat::U16, DatFormat::U8] {
+            let bytes = encode_sections(&sections(), format);
+            assert_eq!(bytes.len(), format.len());
+            assert_eq!(parse_sections(&bytes).unwrap(), sections());
+        }
+
+        let path = crate::tests::temp_dir("dat-round-trip").join("0_0.dat");
+        ChunkData::from(sections().as_ref()).write_dat(&path, DatFormat::U8).unwrap();
+        assert_eq!(ChunkData::read_dat(&path).unwrap().to_raw_sections(), sections());
+    }
+
+    #[test]
+    fn rejects_bad_lengths() {
+        for len in [0, DatFormat::U8.len() - 1, DatFormat::U8.len() + 1, DatFormat::U16.len() - 1] {
+            assert!(matches!(parse_sections(&vec![0; len]), Err(ChunkLoadError::Truncated { len: l }) if l == len));
+        }
+        assert!(matches!(parse_sections(&vec![0; DatFormat::U16.len() + 2]), Err(ChunkLoadError::WrongLength { .. })));
+    }
+
+    #[test]
+    fn unknown_ids() {
+        let mut bytes = vec![0; DatFormat::U16.len()];
+        let index = 16 * 16 * 16 + 0x123;
+        bytes[index * 2..index * 2 + 2].copy_from_slice(&300u16.to_be_bytes());
+        assert!(matches!(parse_sections(&bytes), Err(ChunkLoadError::UnknownBlock { id: 300, index: i, position })
+            if i == index && position == BlockPosition::new(3, 17, 2)));
+
+        let mut registry = BlockRegistry::vanilla();
+        registry.insert(BlockDefinition { id: 300, name: "mod:ore".to_string(), properties: Default::default() }).unwrap();
+        let sections = parse_sections_in(&bytes, &registry).unwrap();
+        assert_eq!(sections[1][0x123], RegistryState::new(300, 0));
+
+        let path = crate::tests::temp_dir("dat-unknown-ids").join("0_0.dat");
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(ChunkData::read_dat_in(&path, &registry).unwrap().get(crate::LocalBlockPosition::new(3, 17, 2)), RegistryState::new(300, 0));
+    }
+}
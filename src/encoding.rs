@@ -0,0 +1,312 @@
+//! Compact binary form of [`ChunkData`].
+//!
+//! All integers are big-endian.
+//!
+//! ```text
+//! header   magic "OCTR", version: u8, index width: u8 (1 = `Nodes::Small`, 2 = `Nodes::Large`)
+//! palette  count: u16, then `count` times { index: width bytes, state: u16 }
+//! sections 24 node streams, bottom to top
+//! node     tag: u8, then
+//!            TAG_BLOCK  index
+//!            TAG_BLOCKS 4 * 4 * 4 indices in yzx order
+//!            TAG_NODES  8 child nodes in `Node::Nodes` order
+//! ```
+//!
+//! A node stream is the pre-order walk of one section's octree. A palette state is
+//! [`BlockState::state_id`]; version 1 stored plain block ids, which still decode with data 0.
+
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+
+use bimap::BiBTreeMap;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{Block, BlockState, ChunkData, leaf_cells, leaf_from_cells, MINIMAL_NODE_SIZE, Node, Nodes, PaletteIndex};
+
+pub const MAGIC: [u8; 4] = *b"OCTR";
+pub const VERSION: u8 = 2;
+
+type SectionNodes<T> = Box<[Node<T>; 24]>;
+
+pub(crate) const TAG_BLOCK: u8 = 0;
+pub(crate) const TAG_BLOCKS: u8 = 1;
+pub(crate) const TAG_NODES: u8 = 2;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(std::io::Error),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u8),
+    UnsupportedIndexWidth(u8),
+    UnknownTag(u8),
+    /// A palette entry with no `Block`, holding a block id in version 1 and a state id since.
+    UnknownBlock(u16),
+    /// A node refers to a palette index that the palette does not define.
+    UnknownIndex(usize),
+    DuplicatePaletteEntry(usize),
+    TrailingBytes(usize),
+    /// A leaf anywhere but at the leaf size, or inner nodes at or below it.
+    MisplacedNode { tag: u8, size: usize },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::BadMagic(magic) => write!(f, "bad magic {:?}", magic),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            Self::UnsupportedIndexWidth(w) => write!(f, "unsupported palette index width {}", w),
+            Self::UnknownTag(tag) => write!(f, "unknown node tag {}", tag),
+            Self::UnknownBlock(id) => write!(f, "unknown block id {}", id),
+            Self::UnknownIndex(i) => write!(f, "palette index {} is not defined", i),
+            Self::DuplicatePaletteEntry(i) => write!(f, "palette entry {} is defined twice", i),
+            Self::TrailingBytes(n) => write!(f, "{} bytes left after the last section", n),
+            Self::MisplacedNode { tag, size } => write!(f, "node tag {} is not allowed in a node {} blocks wide", tag, size),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A palette index that knows its on-disk width.
+trait Encode: PaletteIndex {
+    const WIDTH: u8;
+
+    fn write(self, out: &mut Vec<u8>);
+
+    fn read(r: &mut impl Read) -> std::io::Result<Self>;
+}
+
+impl Encode for u8 {
+    const WIDTH: u8 = 1;
+
+    fn write(self, out: &mut Vec<u8>) {
+        out.push(self);
+    }
+
+    fn read(r: &mut impl Read) -> std::io::Result<Self> {
+        r.read_u8()
+    }
+}
+
+impl Encode for u16 {
+    const WIDTH: u8 = 2;
+
+    fn write(self, out: &mut Vec<u8>) {
+        out.write_u16::<BigEndian>(self).unwrap();
+    }
+
+    fn read(r: &mut impl Read) -> std::io::Result<Self> {
+        r.read_u16::<BigEndian>()
+    }
+}
+
+fn encode_node<T: Encode>(node: &Node<T>, out: &mut Vec<u8>) {
+    match node {
+        Node::Block(id) => {
+            out.push(TAG_BLOCK);
+            id.write(out);
+        }
+        Node::Blocks(blocks) => {
+            out.push(TAG_BLOCKS);
+            leaf_cells(blocks).iter().for_each(|id| id.write(out));
+        }
+        Node::Nodes(children) => {
+            out.push(TAG_NODES);
+            children.iter().for_each(|child| encode_node(child, out));
+        }
+    }
+}
+
+fn encode_nodes<T: Encode>(nodes: &[Node<T>; 24], blocks: &BiBTreeMap<T, BlockState>, out: &mut Vec<u8>) {
+    out.push(T::WIDTH);
+    out.write_u16::<BigEndian>(blocks.len() as u16).unwrap();
+    for (index, block) in blocks {
+        index.write(out);
+        out.write_u16::<BigEndian>(block.state_id()).unwrap();
+    }
+    nodes.iter().for_each(|node| encode_node(node, out));
+}
+
+/// Leaves only exist at [`MINIMAL_NODE_SIZE`] and inner nodes only above it, which also bounds
+/// the depth of a node stream.
+pub(crate) fn check_node_size(tag: u8, size: usize) -> Result<(), DecodeError> {
+    match tag {
+        TAG_BLOCKS if size != MINIMAL_NODE_SIZE => Err(DecodeError::MisplacedNode { tag, size }),
+        TAG_NODES if size <= MINIMAL_NODE_SIZE => Err(DecodeError::MisplacedNode { tag, size }),
+        _ => Ok(()),
+    }
+}
+
+fn check_index<T: PaletteIndex, S: Ord>(blocks: &BiBTreeMap<T, S>, index: T) -> Result<T, DecodeError> {
+    if blocks.contains_left(&index) { Ok(index) } else { Err(DecodeError::UnknownIndex(index.into())) }
+}
+
+/// Checks a tree that did not come from a node stream, such as a deserialized one, like
+/// [`decode_node`] checks a stream. `size` is the edge length of the node, 16 for a section.
+pub(crate) fn check_node<T: PaletteIndex, S: Ord>(node: &Node<T>, blocks: &BiBTreeMap<T, S>, size: usize) -> Result<(), DecodeError> {
+    match node {
+        Node::Block(id) => check_index(blocks, *id).map(drop),
+        Node::Blocks(leaf) => {
+            check_node_size(TAG_BLOCKS, size)?;
+            leaf_cells(leaf).iter().try_for_each(|id| check_index(blocks, *id).map(drop))
+        }
+        Node::Nodes(children) => {
+            check_node_size(TAG_NODES, size)?;
+            children.iter().try_for_each(|child| check_node(child, blocks, size / 2))
+        }
+    }
+}
+
+/// `size` is the edge length of the node, 16 for a section.
+fn decode_node<T: Encode>(r: &mut &[u8], blocks: &BiBTreeMap<T, BlockState>, size: usize) -> Result<Node<T>, DecodeError> {
+    let index = |r: &mut &[u8]| check_index(blocks, T::read(r)?);
+
+    let tag = r.read_u8()?;
+    check_node_size(tag, size)?;
+    match tag {
+        TAG_BLOCK => Ok(Node::Block(index(r)?)),
+        TAG_BLOCKS => {
+            let leaf = (0..MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE)
+                .map(|_| index(r))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::Blocks(leaf_from_cells(&leaf).unwrap()))
+        }
+        TAG_NODES => {
+            let children = (0..8).map(|_| decode_node(r, blocks, size / 2)).collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::Nodes(children.try_into().unwrap()))
+        }
+        tag => Err(DecodeError::UnknownTag(tag)),
+    }
+}
+
+/// `variant` is the `Nodes` constructor matching `T`.
+fn decode_nodes<T: Encode>(r: &mut &[u8], version: u8, variant: fn(SectionNodes<T>, BiBTreeMap<T, BlockState>) -> Nodes) -> Result<Nodes, DecodeError> {
+    let mut blocks = BiBTreeMap::new();
+    for _ in 0..r.read_u16::<BigEndian>()? {
+        let index = T::read(r)?;
+        let id = r.read_u16::<BigEndian>()?;
+        let block = match version {
+            1 => Block::from_id(id).map(BlockState::from),
+            _ => BlockState::from_state_id(id),
+        };
+        let block = block.ok_or(DecodeError::UnknownBlock(id))?;
+        if blocks.insert_no_overwrite(index, block).is_err() {
+            return Err(DecodeError::DuplicatePaletteEntry(index.into()));
+        }
+    }
+
+    let nodes = (0..24).map(|_| decode_node(r, &blocks, 16)).collect::<Result<Box<[_]>, _>>()?;
+    Ok(variant(nodes.try_into().unwrap(), blocks))
+}
+
+impl ChunkData {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        match &self.nodes {
+            Nodes::Large(nodes, blocks) => encode_nodes(nodes, blocks, &mut out),
+            Nodes::Small(nodes, blocks) => encode_nodes(nodes, blocks, &mut out),
+        }
+        out
+    }
+
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let r = &mut bytes;
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(DecodeError::BadMagic(magic));
+        }
+        let version = match r.read_u8()? {
+            v @ (1 | VERSION) => v,
+            v => return Err(DecodeError::UnsupportedVersion(v)),
+        };
+
+        let nodes = match r.read_u8()? {
+            u8::WIDTH => decode_nodes(r, version, Nodes::Small)?,
+            u16::WIDTH => decode_nodes(r, version, Nodes::Large)?,
+            w => return Err(DecodeError::UnsupportedIndexWidth(w)),
+        };
+
+        if !r.is_empty() {
+            return Err(DecodeError::TrailingBytes(r.len()));
+        }
+        Ok(ChunkData { nodes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{section_position, LocalBlockPosition};
+
+    /// Header and a one-entry air palette of a `Nodes::Small` stream, sections still to follow.
+    fn header() -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend([VERSION, u8::WIDTH, 0, 1, 0, 0, 0]);
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let chunk = ChunkData::empty();
+        let decoded = ChunkData::decode(&chunk.encode()).unwrap();
+        assert!(decoded.to_raw_sections() == chunk.to_raw_sections());
+    }
+
+    #[test]
+    fn round_trip_large() {
+        let mut chunk = ChunkData::empty();
+        // a leaf of its own per state in the bottom section, more states than 8-bit indices cover
+        let states = (0..).filter_map(BlockState::from_state_id).take(300);
+        for (i, state) in states.enumerate() {
+            chunk.set_block(section_position(i * 13), state);
+        }
+        // a whole `Block` section and one split into `Block` octants
+        chunk.fill(LocalBlockPosition::new(0, 16, 0), LocalBlockPosition::new(15, 31, 15), Block::Stone.into());
+        chunk.fill(LocalBlockPosition::new(0, 32, 0), LocalBlockPosition::new(7, 39, 7), Block::Dirt.into());
+        assert!(matches!(chunk.nodes, Nodes::Large(..)));
+
+        let bytes = chunk.encode();
+        assert_eq!(bytes[MAGIC.len() + 1], u16::WIDTH);
+        let decoded = ChunkData::decode(&bytes).unwrap();
+        assert!(matches!(decoded.nodes, Nodes::Large(..)));
+        assert!(decoded.to_raw_sections() == chunk.to_raw_sections());
+    }
+
+    #[test]
+    fn rejects_nodes_below_leaf_size() {
+        let mut bytes = header();
+        bytes.extend([TAG_NODES; 3]);
+        assert!(matches!(ChunkData::decode(&bytes), Err(DecodeError::MisplacedNode { tag: TAG_NODES, size: 4 })));
+    }
+
+    #[test]
+    fn rejects_long_nodes_run() {
+        let mut bytes = header();
+        bytes.extend(vec![TAG_NODES; 1 << 20]);
+        assert!(matches!(ChunkData::decode(&bytes), Err(DecodeError::MisplacedNode { .. })));
+    }
+
+    #[test]
+    fn rejects_leaf_above_leaf_size() {
+        let mut bytes = header();
+        bytes.push(TAG_BLOCKS);
+        bytes.extend([0; 64]);
+        assert!(matches!(ChunkData::decode(&bytes), Err(DecodeError::MisplacedNode { tag: TAG_BLOCKS, size: 16 })));
+    }
+}
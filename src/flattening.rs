@@ -0,0 +1,754 @@
+//! Names the 1.13 flattening gave to legacy blocks, for formats that store block states as strings.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::{Block, BlockState, ChunkData, Section, Sections};
+
+pub const NAMESPACE: &str = "minecraft";
+
+/// Legacy block and data value, flattened name, and the one `key=value` property that tells apart
+/// blocks sharing a name, if any. The first entry of a block is its canonical name.
+const TABLE: &[(Block, u8, &str, &str)] = &[
+    (Block::Air, 0, "air", ""),
+    (Block::Stone, 0, "stone", ""),
+    (Block::Stone, 1, "granite", ""),
+    (Block::Stone, 2, "polished_granite", ""),
+    (Block::Stone, 3, "diorite", ""),
+    (Block::Stone, 4, "polished_diorite", ""),
+    (Block::Stone, 5, "andesite", ""),
+    (Block::Stone, 6, "polished_andesite", ""),
+    (Block::Grass, 0, "grass_block", ""),
+    (Block::Dirt, 0, "dirt", ""),
+    (Block::Dirt, 1, "coarse_dirt", ""),
+    (Block::Dirt, 2, "podzol", ""),
+    (Block::Cobblestone, 0, "cobblestone", ""),
+    (Block::Wood, 0, "oak_planks", ""),
+    (Block::Wood, 1, "spruce_planks", ""),
+    (Block::Wood, 2, "birch_planks", ""),
+    (Block::Wood, 3, "jungle_planks", ""),
+    (Block::Wood, 4, "acacia_planks", ""),
+    (Block::Wood, 5, "dark_oak_planks", ""),
+    (Block::Sapling, 0, "oak_sapling", ""),
+    (Block::Sapling, 1, "spruce_sapling", ""),
+    (Block::Sapling, 2, "birch_sapling", ""),
+    (Block::Sapling, 3, "jungle_sapling", ""),
+    (Block::Sapling, 4, "acacia_sapling", ""),
+    (Block::Sapling, 5, "dark_oak_sapling", ""),
+    (Block::Bedrock, 0, "bedrock", ""),
+    (Block::WaterSolid, 0, "water", "level=8"),
+    (Block::Water, 0, "water", ""),
+    (Block::LavaSolid, 0, "lava", "level=8"),
+    (Block::Lava, 0, "lava", ""),
+    (Block::Sand, 0, "sand", ""),
+    (Block::Sand, 1, "red_sand", ""),
+    (Block::Gravel, 0, "gravel", ""),
+    (Block::GoldOre, 0, "gold_ore", ""),
+    (Block::IronOre, 0, "iron_ore", ""),
+    (Block::CoalOre, 0, "coal_ore", ""),
+    (Block::Log, 0, "oak_log", ""),
+    (Block::Log, 1, "spruce_log", ""),
+    (Block::Log, 2, "birch_log", ""),
+    (Block::Log, 3, "jungle_log", ""),
+    (Block::Log, 12, "oak_wood", ""),
+    (Block::Log, 13, "spruce_wood", ""),
+    (Block::Log, 14, "birch_wood", ""),
+    (Block::Log, 15, "jungle_wood", ""),
+    (Block::Leaves, 0, "oak_leaves", ""),
+    (Block::Leaves, 1, "spruce_leaves", ""),
+    (Block::Leaves, 2, "birch_leaves", ""),
+    (Block::Leaves, 3, "jungle_leaves", ""),
+    (Block::Sponge, 0, "sponge", ""),
+    (Block::Sponge, 1, "wet_sponge", ""),
+    (Block::Glass, 0, "glass", ""),
+    (Block::LapisOre, 0, "lapis_ore", ""),
+    (Block::LapisBlock, 0, "lapis_block", ""),
+    (Block::Dispenser, 0, "dispenser", ""),
+    (Block::Sandstone, 0, "sandstone", ""),
+    (Block::Sandstone, 1, "chiseled_sandstone", ""),
+    (Block::Sandstone, 2, "cut_sandstone", ""),
+    (Block::NoteBlock, 0, "note_block", ""),
+    (Block::BedBlock, 0, "red_bed", ""),
+    (Block::BedBlock, 0, "white_bed", ""),
+    (Block::BedBlock, 0, "orange_bed", ""),
+    (Block::BedBlock, 0, "magenta_bed", ""),
+    (Block::BedBlock, 0, "light_blue_bed", ""),
+    (Block::BedBlock, 0, "yellow_bed", ""),
+    (Block::BedBlock, 0, "lime_bed", ""),
+    (Block::BedBlock, 0, "pink_bed", ""),
+    (Block::BedBlock, 0, "gray_bed", ""),
+    (Block::BedBlock, 0, "light_gray_bed", ""),
+    (Block::BedBlock, 0, "cyan_bed", ""),
+    (Block::BedBlock, 0, "purple_bed", ""),
+    (Block::BedBlock, 0, "blue_bed", ""),
+    (Block::BedBlock, 0, "brown_bed", ""),
+    (Block::BedBlock, 0, "green_bed", ""),
+    (Block::BedBlock, 0, "black_bed", ""),
+    (Block::PoweredRail, 0, "powered_rail", ""),
+    (Block::DetectorRail, 0, "detector_rail", ""),
+    (Block::PistonStickyBase, 0, "sticky_piston", ""),
+    (Block::Web, 0, "cobweb", ""),
+    (Block::LongGrassBottom, 1, "grass", ""),
+    (Block::LongGrassBottom, 2, "fern", ""),
+    (Block::LongGrassUp, 0, "dead_bush", ""),
+    (Block::PistonBase, 0, "piston", ""),
+    (Block::PistonExtension, 0, "piston_head", ""),
+    (Block::Wool, 0, "white_wool", ""),
+    (Block::Wool, 1, "orange_wool", ""),
+    (Block::Wool, 2, "magenta_wool", ""),
+    (Block::Wool, 3, "light_blue_wool", ""),
+    (Block::Wool, 4, "yellow_wool", ""),
+    (Block::Wool, 5, "lime_wool", ""),
+    (Block::Wool, 6, "pink_wool", ""),
+    (Block::Wool, 7, "gray_wool", ""),
+    (Block::Wool, 8, "light_gray_wool", ""),
+    (Block::Wool, 9, "cyan_wool", ""),
+    (Block::Wool, 10, "purple_wool", ""),
+    (Block::Wool, 11, "blue_wool", ""),
+    (Block::Wool, 12, "brown_wool", ""),
+    (Block::Wool, 13, "green_wool", ""),
+    (Block::Wool, 14, "red_wool", ""),
+    (Block::Wool, 15, "black_wool", ""),
+    (Block::PistonMovingPiece, 0, "moving_piston", ""),
+    (Block::YellowFlower, 0, "dandelion", ""),
+    (Block::RedRose, 0, "poppy", ""),
+    (Block::RedRose, 1, "blue_orchid", ""),
+    (Block::RedRose, 2, "allium", ""),
+    (Block::RedRose, 3, "azure_bluet", ""),
+    (Block::RedRose, 4, "red_tulip", ""),
+    (Block::RedRose, 5, "orange_tulip", ""),
+    (Block::RedRose, 6, "white_tulip", ""),
+    (Block::RedRose, 7, "pink_tulip", ""),
+    (Block::RedRose, 8, "oxeye_daisy", ""),
+    (Block::BrownMushroom, 0, "brown_mushroom", ""),
+    (Block::RedMushroom, 0, "red_mushroom", ""),
+    (Block::GoldBlock, 0, "gold_block", ""),
+    (Block::IronBlock, 0, "iron_block", ""),
+    (Block::StepDown, 0, "stone_slab", "type=double"),
+    (Block::StepDown, 1, "sandstone_slab", "type=double"),
+    (Block::StepDown, 2, "petrified_oak_slab", "type=double"),
+    (Block::StepDown, 3, "cobblestone_slab", "type=double"),
+    (Block::StepDown, 4, "brick_slab", "type=double"),
+    (Block::StepDown, 5, "stone_brick_slab", "type=double"),
+    (Block::StepDown, 6, "nether_brick_slab", "type=double"),
+    (Block::StepDown, 7, "quartz_slab", "type=double"),
+    (Block::StepUp, 0, "stone_slab", ""),
+    (Block::StepUp, 1, "sandstone_slab", ""),
+    (Block::StepUp, 2, "petrified_oak_slab", ""),
+    (Block::StepUp, 3, "cobblestone_slab", ""),
+    (Block::StepUp, 4, "brick_slab", ""),
+    (Block::StepUp, 5, "stone_brick_slab", ""),
+    (Block::StepUp, 6, "nether_brick_slab", ""),
+    (Block::StepUp, 7, "quartz_slab", ""),
+    (Block::Brick, 0, "bricks", ""),
+    (Block::Tnt, 0, "tnt", ""),
+    (Block::Bookshelf, 0, "bookshelf", ""),
+    (Block::MossyCobblestone, 0, "mossy_cobblestone", ""),
+    (Block::Obsidian, 0, "obsidian", ""),
+    (Block::Torch, 0, "torch", ""),
+    (Block::Torch, 1, "wall_torch", ""),
+    (Block::Fire, 0, "fire", ""),
+    (Block::MobSpawner, 0, "spawner", ""),
+    (Block::WoodStairs, 0, "oak_stairs", ""),
+    (Block::Chest, 0, "chest", ""),
+    (Block::RedstoneWire, 0, "redstone_wire", ""),
+    (Block::DiamondOre, 0, "diamond_ore", ""),
+    (Block::DiamondBlock, 0, "diamond_block", ""),
+    (Block::Workbench, 0, "crafting_table", ""),
+    (Block::Crops, 0, "wheat", ""),
+    (Block::Soil, 0, "farmland", ""),
+    (Block::FurnaceOff, 0, "furnace", ""),
+    (Block::FurnaceOn, 0, "furnace", "lit=true"),
+    (Block::SignPost, 0, "sign", ""),
+    (Block::WoodenDoor, 0, "oak_door", ""),
+    (Block::Ladder, 0, "ladder", ""),
+    (Block::Rails, 0, "rail", ""),
+    (Block::CobblestoneStairs, 0, "cobblestone_stairs", ""),
+    (Block::WallSign, 0, "wall_sign", ""),
+    (Block::Lever, 0, "lever", ""),
+    (Block::StonePlate, 0, "stone_pressure_plate", ""),
+    (Block::IronDoorBlock, 0, "iron_door", ""),
+    (Block::WoodPlate, 0, "oak_pressure_plate", ""),
+    (Block::RedstoneOreOff, 0, "redstone_ore", ""),
+    (Block::RedstoneOreOn, 0, "redstone_ore", "lit=true"),
+    (Block::RedstoneTorchOff, 0, "redstone_torch", "lit=false"),
+    (Block::RedstoneTorchOff, 1, "redstone_wall_torch", "lit=false"),
+    (Block::RedstoneTorchOn, 0, "redstone_torch", ""),
+    (Block::RedstoneTorchOn, 1, "redstone_wall_torch", ""),
+    (Block::StoneButton, 0, "stone_button", ""),
+    (Block::Snow, 0, "snow", ""),
+    (Block::Ice, 0, "ice", ""),
+    (Block::SnowBlock, 0, "snow_block", ""),
+    (Block::Cactus, 0, "cactus", ""),
+    (Block::Clay, 0, "clay", ""),
+    (Block::SugarCaneBlock, 0, "sugar_cane", ""),
+    (Block::Jukebox, 0, "jukebox", ""),
+    (Block::Fence, 0, "oak_fence", ""),
+    (Block::Pumpkin, 0, "carved_pumpkin", ""),
+    (Block::Pumpkin, 0, "pumpkin", ""),
+    (Block::Netherrack, 0, "netherrack", ""),
+    (Block::SoulSand, 0, "soul_sand", ""),
+    (Block::Glowstone, 0, "glowstone", ""),
+    (Block::Portal, 0, "nether_portal", ""),
+    (Block::JackOLantern, 0, "jack_o_lantern", ""),
+    (Block::CakeBlock, 0, "cake", ""),
+    (Block::DiodeBlockOff, 0, "repeater", ""),
+    (Block::DiodeBlockOn, 0, "repeater", "powered=true"),
+    (Block::StainedGlass, 0, "white_stained_glass", ""),
+    (Block::StainedGlass, 1, "orange_stained_glass", ""),
+    (Block::StainedGlass, 2, "magenta_stained_glass", ""),
+    (Block::StainedGlass, 3, "light_blue_stained_glass", ""),
+    (Block::StainedGlass, 4, "yellow_stained_glass", ""),
+    (Block::StainedGlass, 5, "lime_stained_glass", ""),
+    (Block::StainedGlass, 6, "pink_stained_glass", ""),
+    (Block::StainedGlass, 7, "gray_stained_glass", ""),
+    (Block::StainedGlass, 8, "light_gray_stained_glass", ""),
+    (Block::StainedGlass, 9, "cyan_stained_glass", ""),
+    (Block::StainedGlass, 10, "purple_stained_glass", ""),
+    (Block::StainedGlass, 11, "blue_stained_glass", ""),
+    (Block::StainedGlass, 12, "brown_stained_glass", ""),
+    (Block::StainedGlass, 13, "green_stained_glass", ""),
+    (Block::StainedGlass, 14, "red_stained_glass", ""),
+    (Block::StainedGlass, 15, "black_stained_glass", ""),
+    (Block::TrapDoor, 0, "oak_trapdoor", ""),
+    (Block::MonsterEggs, 0, "infested_stone", ""),
+    (Block::MonsterEggs, 1, "infested_cobblestone", ""),
+    (Block::MonsterEggs, 2, "infested_stone_bricks", ""),
+    (Block::MonsterEggs, 3, "infested_mossy_stone_bricks", ""),
+    (Block::MonsterEggs, 4, "infested_cracked_stone_bricks", ""),
+    (Block::MonsterEggs, 5, "infested_chiseled_stone_bricks", ""),
+    (Block::SmoothBrick, 0, "stone_bricks", ""),
+    (Block::SmoothBrick, 1, "mossy_stone_bricks", ""),
+    (Block::SmoothBrick, 2, "cracked_stone_bricks", ""),
+    (Block::SmoothBrick, 3, "chiseled_stone_bricks", ""),
+    (Block::HugeMushroom1, 0, "brown_mushroom_block", ""),
+    (Block::HugeMushroom1, 10, "mushroom_stem", ""),
+    (Block::HugeMushroom2, 0, "red_mushroom_block", ""),
+    (Block::IronFence, 0, "iron_bars", ""),
+    (Block::ThinGlass, 0, "glass_pane", ""),
+    (Block::MelonBlock, 0, "melon_block", ""),
+    (Block::PumpkinStem, 0, "pumpkin_stem", ""),
+    (Block::PumpkinStem, 7, "attached_pumpkin_stem", ""),
+    (Block::MelonStem, 0, "melon_stem", ""),
+    (Block::MelonStem, 7, "attached_melon_stem", ""),
+    (Block::Vine, 0, "vine", ""),
+    (Block::FenceGate, 0, "oak_fence_gate", ""),
+    (Block::BrickStairs, 0, "brick_stairs", ""),
+    (Block::SmoothStairs, 0, "stone_brick_stairs", ""),
+    (Block::Mycel, 0, "mycelium", ""),
+    (Block::WaterLily, 0, "lily_pad", ""),
+    (Block::NetherBrick, 0, "nether_bricks", ""),
+    (Block::NetherFence, 0, "nether_brick_fence", ""),
+    (Block::NetherBrickStairs, 0, "nether_brick_stairs", ""),
+    (Block::NetherWarts, 0, "nether_wart", ""),
+    (Block::EnchantmentTable, 0, "enchanting_table", ""),
+    (Block::BrewingStand, 0, "brewing_stand", ""),
+    (Block::Cauldron, 0, "cauldron", ""),
+    (Block::EnderPortal, 0, "end_portal", ""),
+    (Block::EnderPortalFrame, 0, "end_portal_frame", ""),
+    (Block::EnderStone, 0, "end_stone", ""),
+    (Block::DragonEgg, 0, "dragon_egg", ""),
+    (Block::RedstoneLampOff, 0, "redstone_lamp", ""),
+    (Block::RedstoneLampOn, 0, "redstone_lamp", "lit=true"),
+    (Block::WoodStepDown, 0, "oak_slab", "type=double"),
+    (Block::WoodStepDown, 1, "spruce_slab", "type=double"),
+    (Block::WoodStepDown, 2, "birch_slab", "type=double"),
+    (Block::WoodStepDown, 3, "jungle_slab", "type=double"),
+    (Block::WoodStepDown, 4, "acacia_slab", "type=double"),
+    (Block::WoodStepDown, 5, "dark_oak_slab", "type=double"),
+    (Block::WoodStepUp, 0, "oak_slab", ""),
+    (Block::WoodStepUp, 1, "spruce_slab", ""),
+    (Block::WoodStepUp, 2, "birch_slab", ""),
+    (Block::WoodStepUp, 3, "jungle_slab", ""),
+    (Block::WoodStepUp, 4, "acacia_slab", ""),
+    (Block::WoodStepUp, 5, "dark_oak_slab", ""),
+    (Block::Cocoa, 0, "cocoa", ""),
+    (Block::SandstoneStairs, 0, "sandstone_stairs", ""),
+    (Block::EmeraldOre, 0, "emerald_ore", ""),
+    (Block::EnderChest, 0, "ender_chest", ""),
+    (Block::TripwireHook, 0, "tripwire_hook", ""),
+    (Block::Tripwire, 0, "tripwire", ""),
+    (Block::EmeraldBlock, 0, "emerald_block", ""),
+    (Block::SpruceWoodStairs, 0, "spruce_stairs", ""),
+    (Block::BirchWoodStairs, 0, "birch_stairs", ""),
+    (Block::JungleWoodStairs, 0, "jungle_stairs", ""),
+    (Block::Command, 0, "command_block", ""),
+    (Block::Beacon, 0, "beacon", ""),
+    (Block::CobbleWall, 0, "cobblestone_wall", ""),
+    (Block::CobbleWall, 1, "mossy_cobblestone_wall", ""),
+    (Block::FlowerPot, 0, "flower_pot", ""),
+    (Block::Carrot, 0, "carrots", ""),
+    (Block::Potato, 0, "potatoes", ""),
+    (Block::WoodButton, 0, "oak_button", ""),
+    (Block::Anvil, 0, "anvil", ""),
+    (Block::Anvil, 4, "chipped_anvil", ""),
+    (Block::Anvil, 8, "damaged_anvil", ""),
+    (Block::TrappedChest, 0, "trapped_chest", ""),
+    (Block::GoldPlate, 0, "light_weighted_pressure_plate", ""),
+    (Block::IronPlate, 0, "heavy_weighted_pressure_plate", ""),
+    (Block::RedstoneComparatorOff, 0, "comparator", ""),
+    (Block::RedstoneComparatorOn, 0, "comparator", "powered=true"),
+    (Block::DaylightDetectorOff, 0, "daylight_detector", ""),
+    (Block::RedstoneBlock, 0, "redstone_block", ""),
+    (Block::QuartzOre, 0, "nether_quartz_ore", ""),
+    (Block::Hopper, 0, "hopper", ""),
+    (Block::QuartzBlock, 0, "quartz_block", ""),
+    (Block::QuartzBlock, 1, "chiseled_quartz_block", ""),
+    (Block::QuartzBlock, 2, "quartz_pillar", ""),
+    (Block::QuartzStairs, 0, "quartz_stairs", ""),
+    (Block::ActivatorRail, 0, "activator_rail", ""),
+    (Block::Dropper, 0, "dropper", ""),
+    (Block::StainedClay, 0, "white_terracotta", ""),
+    (Block::StainedClay, 1, "orange_terracotta", ""),
+    (Block::StainedClay, 2, "magenta_terracotta", ""),
+    (Block::StainedClay, 3, "light_blue_terracotta", ""),
+    (Block::StainedClay, 4, "yellow_terracotta", ""),
+    (Block::StainedClay, 5, "lime_terracotta", ""),
+    (Block::StainedClay, 6, "pink_terracotta", ""),
+    (Block::StainedClay, 7, "gray_terracotta", ""),
+    (Block::StainedClay, 8, "light_gray_terracotta", ""),
+    (Block::StainedClay, 9, "cyan_terracotta", ""),
+    (Block::StainedClay, 10, "purple_terracotta", ""),
+    (Block::StainedClay, 11, "blue_terracotta", ""),
+    (Block::StainedClay, 12, "brown_terracotta", ""),
+    (Block::StainedClay, 13, "green_terracotta", ""),
+    (Block::StainedClay, 14, "red_terracotta", ""),
+    (Block::StainedClay, 15, "black_terracotta", ""),
+    (Block::StainedGlassPane, 0, "white_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 1, "orange_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 2, "magenta_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 3, "light_blue_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 4, "yellow_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 5, "lime_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 6, "pink_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 7, "gray_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 8, "light_gray_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 9, "cyan_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 10, "purple_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 11, "blue_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 12, "brown_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 13, "green_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 14, "red_stained_glass_pane", ""),
+    (Block::StainedGlassPane, 15, "black_stained_glass_pane", ""),
+    (Block::Leaves2, 0, "acacia_leaves", ""),
+    (Block::Leaves2, 1, "dark_oak_leaves", ""),
+    (Block::Log2, 0, "acacia_log", ""),
+    (Block::Log2, 1, "dark_oak_log", ""),
+    (Block::Log2, 12, "acacia_wood", ""),
+    (Block::Log2, 13, "dark_oak_wood", ""),
+    (Block::AcaciaStairs, 0, "acacia_stairs", ""),
+    (Block::DarkOakStairs, 0, "dark_oak_stairs", ""),
+    (Block::SlimeBlock, 0, "slime_block", ""),
+    (Block::Barrier, 0, "barrier", ""),
+    (Block::IronTrapdoor, 0, "iron_trapdoor", ""),
+    (Block::Prismarine, 0, "prismarine", ""),
+    (Block::Prismarine, 1, "prismarine_bricks", ""),
+    (Block::Prismarine, 2, "dark_prismarine", ""),
+    (Block::SeaLantern, 0, "sea_lantern", ""),
+    (Block::HayBlock, 0, "hay_block", ""),
+    (Block::Carpet, 0, "white_carpet", ""),
+    (Block::Carpet, 1, "orange_carpet", ""),
+    (Block::Carpet, 2, "magenta_carpet", ""),
+    (Block::Carpet, 3, "light_blue_carpet", ""),
+    (Block::Carpet, 4, "yellow_carpet", ""),
+    (Block::Carpet, 5, "lime_carpet", ""),
+    (Block::Carpet, 6, "pink_carpet", ""),
+    (Block::Carpet, 7, "gray_carpet", ""),
+    (Block::Carpet, 8, "light_gray_carpet", ""),
+    (Block::Carpet, 9, "cyan_carpet", ""),
+    (Block::Carpet, 10, "purple_carpet", ""),
+    (Block::Carpet, 11, "blue_carpet", ""),
+    (Block::Carpet, 12, "brown_carpet", ""),
+    (Block::Carpet, 13, "green_carpet", ""),
+    (Block::Carpet, 14, "red_carpet", ""),
+    (Block::Carpet, 15, "black_carpet", ""),
+    (Block::HardClay, 0, "terracotta", ""),
+    (Block::CoalBlock, 0, "coal_block", ""),
+    (Block::PackedIce, 0, "packed_ice", ""),
+    (Block::DoublePlant, 0, "sunflower", ""),
+    (Block::DoublePlant, 1, "lilac", ""),
+    (Block::DoublePlant, 2, "tall_grass", ""),
+    (Block::DoublePlant, 3, "large_fern", ""),
+    (Block::DoublePlant, 4, "rose_bush", ""),
+    (Block::DoublePlant, 5, "peony", ""),
+    (Block::StandingBanner, 0, "white_banner", ""),
+    (Block::StandingBanner, 0, "orange_banner", ""),
+    (Block::StandingBanner, 0, "magenta_banner", ""),
+    (Block::StandingBanner, 0, "light_blue_banner", ""),
+    (Block::StandingBanner, 0, "yellow_banner", ""),
+    (Block::StandingBanner, 0, "lime_banner", ""),
+    (Block::StandingBanner, 0, "pink_banner", ""),
+    (Block::StandingBanner, 0, "gray_banner", ""),
+    (Block::StandingBanner, 0, "light_gray_banner", ""),
+    (Block::StandingBanner, 0, "cyan_banner", ""),
+    (Block::StandingBanner, 0, "purple_banner", ""),
+    (Block::StandingBanner, 0, "blue_banner", ""),
+    (Block::StandingBanner, 0, "brown_banner", ""),
+    (Block::StandingBanner, 0, "green_banner", ""),
+    (Block::StandingBanner, 0, "red_banner", ""),
+    (Block::StandingBanner, 0, "black_banner", ""),
+    (Block::WallBanner, 0, "white_wall_banner", ""),
+    (Block::WallBanner, 0, "orange_wall_banner", ""),
+    (Block::WallBanner, 0, "magenta_wall_banner", ""),
+    (Block::WallBanner, 0, "light_blue_wall_banner", ""),
+    (Block::WallBanner, 0, "yellow_wall_banner", ""),
+    (Block::WallBanner, 0, "lime_wall_banner", ""),
+    (Block::WallBanner, 0, "pink_wall_banner", ""),
+    (Block::WallBanner, 0, "gray_wall_banner", ""),
+    (Block::WallBanner, 0, "light_gray_wall_banner", ""),
+    (Block::WallBanner, 0, "cyan_wall_banner", ""),
+    (Block::WallBanner, 0, "purple_wall_banner", ""),
+    (Block::WallBanner, 0, "blue_wall_banner", ""),
+    (Block::WallBanner, 0, "brown_wall_banner", ""),
+    (Block::WallBanner, 0, "green_wall_banner", ""),
+    (Block::WallBanner, 0, "red_wall_banner", ""),
+    (Block::WallBanner, 0, "black_wall_banner", ""),
+    (Block::DaylightDetectorOn, 0, "daylight_detector", "inverted=true"),
+    (Block::RedSandstone, 0, "red_sandstone", ""),
+    (Block::RedSandstone, 1, "chiseled_red_sandstone", ""),
+    (Block::RedSandstone, 2, "cut_red_sandstone", ""),
+    (Block::RedSandstoneStairs, 0, "red_sandstone_stairs", ""),
+    (Block::StoneSlab2, 0, "red_sandstone_slab", "type=double"),
+    (Block::SpruceFenceGate, 0, "spruce_fence_gate", ""),
+    (Block::BirchFenceGate, 0, "birch_fence_gate", ""),
+    (Block::JungleFenceGate, 0, "jungle_fence_gate", ""),
+    (Block::DarkOakFenceGate, 0, "dark_oak_fence_gate", ""),
+    (Block::AcaciaFenceGate, 0, "acacia_fence_gate", ""),
+    (Block::SpruceFence, 0, "spruce_fence", ""),
+    (Block::BirchFence, 0, "birch_fence", ""),
+    (Block::JungleFence, 0, "jungle_fence", ""),
+    (Block::DarkOakFence, 0, "dark_oak_fence", ""),
+    (Block::AcaciaFence, 0, "acacia_fence", ""),
+    (Block::SpruceDoor, 0, "spruce_door", ""),
+    (Block::BirchDoor, 0, "birch_door", ""),
+    (Block::JungleDoor, 0, "jungle_door", ""),
+    (Block::AcaciaDoor, 0, "acacia_door", ""),
+    (Block::DarkOakDoor, 0, "dark_oak_door", ""),
+    (Block::EndRod, 0, "end_rod", ""),
+    (Block::ChorusPlant, 0, "chorus_plant", ""),
+    (Block::ChorusFlower, 0, "chorus_flower", ""),
+    (Block::PurpurBlock, 0, "purpur_block", ""),
+    (Block::PurpurPillar, 0, "purpur_pillar", ""),
+    (Block::PurpurStairs, 0, "purpur_stairs", ""),
+    (Block::PurpurSlabBottom, 0, "purpur_slab", "type=double"),
+    (Block::PurpurSlabUp, 0, "purpur_slab", ""),
+    (Block::EndBricks, 0, "end_stone_bricks", ""),
+    (Block::BeetrootBlock, 0, "beetroots", ""),
+    (Block::GrassPath, 0, "grass_path", ""),
+    (Block::EndGateway, 0, "end_gateway", ""),
+    (Block::CommandRepeating, 0, "repeating_command_block", ""),
+    (Block::CommandChain, 0, "chain_command_block", ""),
+    (Block::FrostedIce, 0, "frosted_ice", ""),
+    (Block::Magma, 0, "magma_block", ""),
+    (Block::NetherWartBlock, 0, "nether_wart_block", ""),
+    (Block::RedNetherBrick, 0, "red_nether_bricks", ""),
+    (Block::BoneBlock, 0, "bone_block", ""),
+    (Block::StructureVoid, 0, "structure_void", ""),
+    (Block::Observer, 0, "observer", ""),
+    (Block::WhiteShulkerBox, 0, "white_shulker_box", ""),
+    (Block::OrangeShulkerBox, 0, "orange_shulker_box", ""),
+    (Block::MagentaShulkerBox, 0, "magenta_shulker_box", ""),
+    (Block::LightBlueShulkerBox, 0, "light_blue_shulker_box", ""),
+    (Block::YellowShulkerBox, 0, "yellow_shulker_box", ""),
+    (Block::LimeShulkerBox, 0, "lime_shulker_box", ""),
+    (Block::PinkShulkerBox, 0, "pink_shulker_box", ""),
+    (Block::GrayShulkerBox, 0, "gray_shulker_box", ""),
+    (Block::SilverShulkerBox, 0, "light_gray_shulker_box", ""),
+    (Block::CyanShulkerBox, 0, "cyan_shulker_box", ""),
+    (Block::PurpleShulkerBox, 0, "purple_shulker_box", ""),
+    (Block::BlueShulkerBox, 0, "blue_shulker_box", ""),
+    (Block::BrownShulkerBox, 0, "brown_shulker_box", ""),
+    (Block::GreenShulkerBox, 0, "green_shulker_box", ""),
+    (Block::RedShulkerBox, 0, "red_shulker_box", ""),
+    (Block::BlackShulkerBox, 0, "black_shulker_box", ""),
+    (Block::WhiteGlazedTerracotta, 0, "white_glazed_terracotta", ""),
+    (Block::OrangeGlazedTerracotta, 0, "orange_glazed_terracotta", ""),
+    (Block::MagentaGlazedTerracotta, 0, "magenta_glazed_terracotta", ""),
+    (Block::LightBlueGlazedTerracotta, 0, "light_blue_glazed_terracotta", ""),
+    (Block::YellowGlazedTerracotta, 0, "yellow_glazed_terracotta", ""),
+    (Block::LimeGlazedTerracotta, 0, "lime_glazed_terracotta", ""),
+    (Block::PinkGlazedTerracotta, 0, "pink_glazed_terracotta", ""),
+    (Block::GrayGlazedTerracotta, 0, "gray_glazed_terracotta", ""),
+    (Block::SilverGlazedTerracotta, 0, "light_gray_glazed_terracotta", ""),
+    (Block::CyanGlazedTerracotta, 0, "cyan_glazed_terracotta", ""),
+    (Block::PurpleGlazedTerracotta, 0, "purple_glazed_terracotta", ""),
+    (Block::BlueGlazedTerracotta, 0, "blue_glazed_terracotta", ""),
+    (Block::BrownGlazedTerracotta, 0, "brown_glazed_terracotta", ""),
+    (Block::GreenGlazedTerracotta, 0, "green_glazed_terracotta", ""),
+    (Block::RedGlazedTerracotta, 0, "red_glazed_terracotta", ""),
+    (Block::BlackGlazedTerracotta, 0, "black_glazed_terracotta", ""),
+    (Block::Concrete, 0, "white_concrete", ""),
+    (Block::Concrete, 1, "orange_concrete", ""),
+    (Block::Concrete, 2, "magenta_concrete", ""),
+    (Block::Concrete, 3, "light_blue_concrete", ""),
+    (Block::Concrete, 4, "yellow_concrete", ""),
+    (Block::Concrete, 5, "lime_concrete", ""),
+    (Block::Concrete, 6, "pink_concrete", ""),
+    (Block::Concrete, 7, "gray_concrete", ""),
+    (Block::Concrete, 8, "light_gray_concrete", ""),
+    (Block::Concrete, 9, "cyan_concrete", ""),
+    (Block::Concrete, 10, "purple_concrete", ""),
+    (Block::Concrete, 11, "blue_concrete", ""),
+    (Block::Concrete, 12, "brown_concrete", ""),
+    (Block::Concrete, 13, "green_concrete", ""),
+    (Block::Concrete, 14, "red_concrete", ""),
+    (Block::Concrete, 15, "black_concrete", ""),
+    (Block::ConcretePowder, 0, "white_concrete_powder", ""),
+    (Block::ConcretePowder, 1, "orange_concrete_powder", ""),
+    (Block::ConcretePowder, 2, "magenta_concrete_powder", ""),
+    (Block::ConcretePowder, 3, "light_blue_concrete_powder", ""),
+    (Block::ConcretePowder, 4, "yellow_concrete_powder", ""),
+    (Block::ConcretePowder, 5, "lime_concrete_powder", ""),
+    (Block::ConcretePowder, 6, "pink_concrete_powder", ""),
+    (Block::ConcretePowder, 7, "gray_concrete_powder", ""),
+    (Block::ConcretePowder, 8, "light_gray_concrete_powder", ""),
+    (Block::ConcretePowder, 9, "cyan_concrete_powder", ""),
+    (Block::ConcretePowder, 10, "purple_concrete_powder", ""),
+    (Block::ConcretePowder, 11, "blue_concrete_powder", ""),
+    (Block::ConcretePowder, 12, "brown_concrete_powder", ""),
+    (Block::ConcretePowder, 13, "green_concrete_powder", ""),
+    (Block::ConcretePowder, 14, "red_concrete_powder", ""),
+    (Block::ConcretePowder, 15, "black_concrete_powder", ""),
+    (Block::StructureBlock, 0, "structure_block", ""),
+];
+
+/// Data bits that hold a property rather than pick the variant in [`TABLE`], with the value each
+/// bit pattern stands for. Patterns not listed leave the bits to the table, e.g. the all-bark logs.
+struct PropertyBits {
+    mask: u8,
+    key: &'static str,
+    values: &'static [(u8, &'static str)],
+}
+
+const STAIRS: &[PropertyBits] = &[
+    PropertyBits { mask: 0x3, key: "facing", values: &[(0, "east"), (1, "west"), (2, "south"), (3, "north")] },
+    PropertyBits { mask: 0x4, key: "half", values: &[(0, "bottom"), (4, "top")] },
+];
+const LOG: &[PropertyBits] = &[
+    PropertyBits { mask: 0xc, key: "axis", values: &[(0, "y"), (4, "x"), (8, "z")] },
+];
+const SLAB: &[PropertyBits] = &[
+    PropertyBits { mask: 0x8, key: "type", values: &[(0, "bottom"), (8, "top")] },
+];
+const HORIZONTAL: &[PropertyBits] = &[
+    PropertyBits { mask: 0x7, key: "facing", values: &[(2, "north"), (3, "south"), (4, "west"), (5, "east")] },
+];
+
+fn property_bits(block: Block) -> &'static [PropertyBits] {
+    use Block::*;
+    match block {
+        WoodStairs | CobblestoneStairs | BrickStairs | SmoothStairs | NetherBrickStairs | SandstoneStairs
+        | SpruceWoodStairs | BirchWoodStairs | JungleWoodStairs | QuartzStairs | AcaciaStairs | DarkOakStairs
+        | RedSandstoneStairs | PurpurStairs => STAIRS,
+        Log | Log2 => LOG,
+        StepUp | WoodStepUp | PurpurSlabUp => SLAB,
+        Chest | TrappedChest | EnderChest | FurnaceOff | FurnaceOn => HORIZONTAL,
+        _ => &[],
+    }
+}
+
+/// A 1.13+ block state such as `minecraft:oak_stairs[facing=north,half=top]`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+pub struct FlatBlockState {
+    pub namespace: String,
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFlatStateError(pub String);
+
+impl Display for ParseFlatStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "malformed block state {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFlatStateError {}
+
+impl FlatBlockState {
+    pub fn new(namespace: impl Into<String>, name: impl Into<String>) -> Self {
+        Self { namespace: namespace.into(), name: name.into(), properties: BTreeMap::new() }
+    }
+
+    #[allow(dead_code)]
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// The legacy state this one was flattened from. Properties that 1.12 kept outside the data
+    /// value, such as stair shapes, are ignored. `None` for blocks added after 1.12 and other namespaces.
+    pub fn to_legacy(&self) -> Option<BlockState> {
+        if self.namespace != NAMESPACE {
+            return None;
+        }
+        let candidates = TABLE.iter().filter(|(_, _, n, _)| *n == self.name);
+        let (matching, plain): (Vec<&(Block, u8, &str, &str)>, Vec<_>) = candidates.partition(|(.., property)| !property.is_empty());
+        let (block, data, ..) = matching.iter()
+            .find(|(.., property)| property.split_once('=').is_some_and(|(k, v)| self.property(k) == Some(v)))
+            .or(plain.first())
+            .or(matching.first())?;
+
+        let data = property_bits(*block).iter().fold(*data, |data, bits| {
+            match bits.values.iter().find(|(_, v)| self.property(bits.key) == Some(*v)) {
+                Some((value, _)) => data & !bits.mask | value,
+                None => data,
+            }
+        });
+        Some(BlockState::new(*block, data))
+    }
+}
+
+impl From<BlockState> for FlatBlockState {
+    /// Data values the table does not list fall back to the block's canonical name.
+    fn from(state: BlockState) -> Self {
+        let mut properties = BTreeMap::new();
+        let mut variant = state.data;
+        for bits in property_bits(state.block) {
+            if let Some((_, value)) = bits.values.iter().find(|(v, _)| *v == state.data & bits.mask) {
+                properties.insert(bits.key.to_string(), value.to_string());
+                variant &= !bits.mask;
+            }
+        }
+
+        let (_, _, name, property) = TABLE.iter()
+            .find(|(b, data, ..)| *b == state.block && *data == variant)
+            .or_else(|| TABLE.iter().find(|(b, ..)| *b == state.block))
+            .unwrap();
+        if let Some((key, value)) = property.split_once('=') {
+            properties.insert(key.to_string(), value.to_string());
+        }
+        Self { namespace: NAMESPACE.to_string(), name: name.to_string(), properties }
+    }
+}
+
+impl Display for FlatBlockState {
+    /// Properties come sorted by key, as vanilla writes them.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.name)?;
+        if !self.properties.is_empty() {
+            let properties: Vec<String> = self.properties.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FlatBlockState {
+    type Err = ParseFlatStateError;
+
+    /// A missing namespace means `minecraft`.
+    fn from_str(state: &str) -> Result<Self, Self::Err> {
+        let error = || ParseFlatStateError(state.to_string());
+        let (id, properties) = match state.split_once('[') {
+            Some((id, properties)) => (id, Some(properties.strip_suffix(']').ok_or_else(error)?)),
+            None => (state, None),
+        };
+        let (namespace, name) = id.split_once(':').unwrap_or((NAMESPACE, id));
+        if namespace.is_empty() || name.is_empty() {
+            return Err(error());
+        }
+
+        let mut flat = Self::new(namespace, name);
+        for property in properties.iter().flat_map(|p| p.split(',')).filter(|p| !p.trim().is_empty()) {
+            let (key, value) = property.split_once('=').ok_or_else(error)?;
+            flat.properties.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Ok(flat)
+    }
+}
+
+/// Every flattened name of a legacy block, without namespace, for suggestions.
+pub(crate) fn flat_names() -> impl Iterator<Item=&'static str> {
+    TABLE.iter().map(|(_, _, name, _)| *name)
+}
+
+/// Canonical state string of `state`, such as `minecraft:oak_stairs[facing=north,half=top]`.
+pub fn flat_name(state: BlockState) -> String {
+    FlatBlockState::from(state).to_string()
+}
+
+/// Maps a state string back to a legacy block state, see [`FlatBlockState::to_legacy`].
+pub fn parse_flat_name(state: &str) -> Option<BlockState> {
+    state.parse::<FlatBlockState>().ok()?.to_legacy()
+}
+
+impl ChunkData {
+    /// The same blocks with a palette of flattened states. Legacy states that flatten alike share
+    /// an entry, so the octrees are rebuilt.
+    #[allow(dead_code)]
+    pub fn to_flat(&self) -> ChunkData<FlatBlockState> {
+        let mut cache = HashMap::new();
+        let sections: Box<[Section<FlatBlockState>]> = self.to_raw_sections().iter()
+            .map(|section| section.each_ref().map(|b| cache.entry(*b).or_insert_with(|| FlatBlockState::from(*b)).clone()))
+            .collect();
+        let sections: Box<Sections<FlatBlockState>> = sections.try_into().unwrap_or_else(|_| unreachable!());
+        ChunkData::from(sections.as_ref())
+    }
+}
+
+impl ChunkData<FlatBlockState> {
+    /// Back to legacy states. States without one become air and are returned alongside.
+    #[allow(dead_code)]
+    pub fn to_legacy(&self) -> (ChunkData, BTreeSet<FlatBlockState>) {
+        let mut unmapped = BTreeSet::new();
+        let mut cache = HashMap::new();
+        let sections: Box<[crate::RawSection]> = self.to_raw_sections().iter()
+            .map(|section| section.each_ref().map(|flat| *cache.entry(flat.clone()).or_insert_with(|| flat.to_legacy().unwrap_or_else(|| {
+                unmapped.insert(flat.clone());
+                BlockState::AIR
+            }))))
+            .collect();
+        let sections: Box<crate::RawSections> = sections.try_into().unwrap();
+        (ChunkData::from(sections.as_ref()), unmapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalBlockPosition;
+
+    #[test]
+    fn table_round_trip() {
+        for (block, data, ..) in TABLE {
+            let state = BlockState::new(*block, *data);
+            assert_eq!(parse_flat_name(&flat_name(state)), Some(state), "{}", flat_name(state));
+        }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let stairs = FlatBlockState::new(NAMESPACE, "oak_stairs").with("half", "top").with("facing", "north");
+        assert_eq!(stairs.to_string(), "minecraft:oak_stairs[facing=north,half=top]");
+        assert_eq!("oak_stairs[ half=top , facing=north ]".parse::<FlatBlockState>().unwrap(), stairs);
+        // facing=north is 3, half=top is 4
+        assert_eq!(stairs.to_legacy(), Some(BlockState::new(Block::WoodStairs, 7)));
+        // spruce is 1, axis=x is 4
+        let log: FlatBlockState = "spruce_log[axis=x]".parse().unwrap();
+        assert_eq!(log.to_legacy(), Some(BlockState::new(Block::Log, 5)));
+        assert_eq!(FlatBlockState::from(BlockState::new(Block::Log, 5)), log);
+        assert!("minecraft:".parse::<FlatBlockState>().is_err());
+        assert!("stone[variant".parse::<FlatBlockState>().is_err());
+        assert_eq!(parse_flat_name("mod:stone"), None);
+    }
+
+    #[test]
+    fn chunk_round_trip() {
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(LocalBlockPosition::new(1, 1, 1), BlockState::new(Block::Wool, 5));
+        let mut flat = chunk.to_flat();
+        assert_eq!(flat.get(LocalBlockPosition::new(1, 1, 1)).to_string(), "minecraft:lime_wool");
+
+        flat.set_block(LocalBlockPosition::new(2, 2, 2), FlatBlockState::new("mod", "ore"));
+        let (legacy, unmapped) = flat.to_legacy();
+        assert_eq!(legacy.to_raw_sections(), chunk.to_raw_sections());
+        assert_eq!(unmapped, BTreeSet::from([FlatBlockState::new("mod", "ore")]));
+    }
+}
@@ -0,0 +1,202 @@
+//! A [`World`](crate::world::World) alternative that only builds chunk octrees when they are first used.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{BlockPosition, BlockState, ChunkData, ChunkPosition};
+use crate::dat::{ChunkLoadError, parse_sections};
+use crate::mmap::Mmap;
+use crate::world::{parse_chunk_file_name, WORLD_HEIGHT};
+
+type EvictHook = Box<dyn FnMut(ChunkPosition, Arc<ChunkData>) + Send>;
+
+// The mapping a chunk was decoded from stays alive with the chunk and is only unmapped once the
+// eviction hook has seen it.
+struct Cached {
+    chunk: Arc<ChunkData>,
+    _map: Mmap,
+}
+
+#[derive(Default)]
+struct Cache {
+    chunks: HashMap<ChunkPosition, Cached>,
+    // load order, oldest first
+    order: VecDeque<ChunkPosition>,
+}
+
+/// Opening only lists the `{x}_{z}.dat` files of a directory. A chunk file is memory-mapped and decoded
+/// on first access, and both are kept until evicted, either explicitly or by going over the capacity.
+pub struct LazyWorld {
+    files: HashMap<ChunkPosition, PathBuf>,
+    cache: Mutex<Cache>,
+    capacity: usize,
+    on_evict: Mutex<Option<EvictHook>>,
+}
+
+impl LazyWorld {
+    pub fn open(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut files = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if let Some(pos) = parse_chunk_file_name(&path) {
+                files.insert(pos, path);
+            }
+        }
+        Ok(Self { files, cache: Mutex::default(), capacity: usize::MAX, on_evict: Mutex::new(None) })
+    }
+
+    /// Caps the number of decoded chunks kept, evicting the oldest ones first.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Called with every chunk that leaves the cache, e.g. to keep it elsewhere. Its file stays
+    /// mapped until the hook returns.
+    pub fn on_evict(&self, hook: impl FnMut(ChunkPosition, Arc<ChunkData>) + Send + 'static) {
+        *self.on_evict.lock().unwrap() = Some(Box::new(hook));
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item=&ChunkPosition> {
+        self.files.keys()
+    }
+
+    /// Number of chunks currently decoded.
+    pub fn loaded(&self) -> usize {
+        self.cache.lock().unwrap().chunks.len()
+    }
+
+    pub fn is_loaded(&self, pos: ChunkPosition) -> bool {
+        self.cache.lock().unwrap().chunks.contains_key(&pos)
+    }
+
+    /// Decodes the chunk if it is not cached yet. `None` if the directory has no file for it.
+    pub fn chunk(&self, pos: ChunkPosition) -> Result<Option<Arc<ChunkData>>, ChunkLoadError> {
+        if let Some(cached) = self.cache.lock().unwrap().chunks.get(&pos) {
+            return Ok(Some(cached.chunk.clone()));
+        }
+        let Some(path) = self.files.get(&pos) else { return Ok(None) };
+
+        // Decode without holding the lock, another thread may beat us to it.
+        let map = Mmap::open(path)?;
+        let decoded = Arc::new(ChunkData::from(parse_sections(&map)?.as_ref()));
+
+        let mut evicted = vec![];
+        let chunk = {
+            let mut cache = self.cache.lock().unwrap();
+            let chunk = match cache.chunks.get(&pos) {
+                Some(cached) => cached.chunk.clone(),
+                None => {
+                    cache.chunks.insert(pos, Cached { chunk: decoded.clone(), _map: map });
+                    cache.order.push_back(pos);
+                    decoded
+                }
+            };
+            while cache.chunks.len() > self.capacity {
+                let oldest = cache.order.pop_front().unwrap();
+                evicted.extend(cache.chunks.remove(&oldest).map(|c| (oldest, c)));
+            }
+            chunk
+        };
+
+        self.notify(evicted);
+        Ok(Some(chunk))
+    }
+
+    pub fn get(&self, pos: BlockPosition) -> Result<Option<BlockState>, ChunkLoadError> {
+        if pos.y as u32 >= WORLD_HEIGHT {
+            return Ok(None);
+        }
+        Ok(self.chunk(pos.into())?.map(|chunk| chunk.get(pos.into())))
+    }
+
+    pub fn evict(&self, pos: ChunkPosition) -> Option<Arc<ChunkData>> {
+        let cached = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.order.retain(|p| *p != pos);
+            cache.chunks.remove(&pos)
+        }?;
+        let chunk = cached.chunk.clone();
+        self.notify(vec![(pos, cached)]);
+        Some(chunk)
+    }
+
+    pub fn evict_all(&self) {
+        let evicted = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.order.clear();
+            cache.chunks.drain().collect()
+        };
+        self.notify(evicted);
+    }
+
+    // The mappings are dropped here, after the hook.
+    fn notify(&self, evicted: Vec<(ChunkPosition, Cached)>) {
+        if evicted.is_empty() {
+            return;
+        }
+        if let Some(hook) = self.on_evict.lock().unwrap().as_mut() {
+            for (pos, cached) in evicted {
+                hook(pos, cached.chunk.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, LocalBlockPosition};
+    use crate::dat::DatFormat;
+
+    fn world(name: &str) -> LazyWorld {
+        let dir = crate::tests::temp_dir(name);
+        for (x, block) in [Block::Stone, Block::Dirt, Block::Wool].into_iter().enumerate() {
+            let mut chunk = ChunkData::empty();
+            chunk.set_block(LocalBlockPosition::new(0, 0, 0), BlockState::from(block));
+            chunk.write_dat(dir.join(format!("{}_0.dat", x)), DatFormat::U8).unwrap();
+        }
+        LazyWorld::open(dir).unwrap()
+    }
+
+    #[test]
+    fn decodes_on_access() {
+        let world = world("lazy-world-access");
+        assert_eq!((world.len(), world.loaded()), (3, 0));
+        assert!(!world.is_empty());
+        assert_eq!(world.positions().count(), 3);
+
+        assert_eq!(world.get(BlockPosition::new(32, 0, 0)).unwrap(), Some(BlockState::from(Block::Wool)));
+        assert!(world.is_loaded(ChunkPosition::new(2, 0)));
+        assert_eq!(world.get(BlockPosition::new(48, 0, 0)).unwrap(), None);
+        assert_eq!(world.get(BlockPosition::new(0, WORLD_HEIGHT, 0)).unwrap(), None);
+        assert_eq!(world.loaded(), 1);
+    }
+
+    #[test]
+    fn evicts_oldest_first() {
+        let world = world("lazy-world-evict").with_capacity(2);
+        let evicted = Arc::new(Mutex::new(vec![]));
+        let hook = evicted.clone();
+        world.on_evict(move |pos, _| hook.lock().unwrap().push(pos));
+
+        for x in 0..3 {
+            world.chunk(ChunkPosition::new(x, 0)).unwrap();
+        }
+        assert_eq!(*evicted.lock().unwrap(), [ChunkPosition::new(0, 0)]);
+        assert!(world.evict(ChunkPosition::new(2, 0)).is_some());
+        assert!(world.evict(ChunkPosition::new(2, 0)).is_none());
+        world.evict_all();
+        assert_eq!(world.loaded(), 0);
+        assert_eq!(evicted.lock().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,101 @@
+//! Compares octrees built with different `Blocks` leaf sizes, to pick [`MINIMAL_NODE_SIZE`].
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use get_size::GetSize;
+
+use crate::{LocalBlockPosition, MINIMAL_NODE_SIZE, Node, Nodes, PaletteIndex, Section};
+use crate::world::{World, WorldLoadError};
+
+#[derive(Copy, Clone, Debug)]
+pub struct LeafSizeStats {
+    /// Edge length of a leaf, [`MINIMAL_NODE_SIZE`] being the default.
+    pub leaf: usize,
+    /// Building every section from its flat palette indices.
+    pub build: Duration,
+    /// [`Node::get`] of every block of every section.
+    pub lookup: Duration,
+    /// [`GetSize`] of all sections, without their palettes.
+    pub bytes: usize,
+}
+
+/// Rebuilds every section of the `{x}_{z}.dat` chunks in `dir` with each possible leaf size,
+/// keeping the palette index width the chunk has. Runs on a single thread so timings compare.
+pub fn report(dir: impl AsRef<Path>) -> Result<Vec<LeafSizeStats>, WorldLoadError> {
+    let world = World::load(dir)?;
+    let (mut small, mut large) = (vec![], vec![]);
+    for (_, chunk) in world.chunks() {
+        match &chunk.nodes {
+            Nodes::Small(nodes, _) => small.extend(nodes.iter().map(Node::to_section)),
+            Nodes::Large(nodes, _) => large.extend(nodes.iter().map(Node::to_section)),
+        }
+    }
+
+    let stats = [measure::<1>, measure::<2>, measure::<4>, measure::<8>, measure::<16>]
+        .map(|measure| measure(&small, &large));
+    debug_assert!(stats.iter().any(|s| s.leaf == MINIMAL_NODE_SIZE));
+    Ok(stats.to_vec())
+}
+
+fn measure<const LEAF: usize>(small: &[Section<u8>], large: &[Section<u16>]) -> LeafSizeStats {
+    let (small_build, small) = build::<u8, LEAF>(small);
+    let (large_build, large) = build::<u16, LEAF>(large);
+    LeafSizeStats {
+        leaf: LEAF,
+        build: small_build + large_build,
+        lookup: lookup(&small) + lookup(&large),
+        bytes: small.iter().map(Node::get_size).sum::<usize>() + large.iter().map(Node::get_size).sum::<usize>(),
+    }
+}
+
+fn build<T: PaletteIndex, const LEAF: usize>(sections: &[Section<T>]) -> (Duration, Vec<Node<T, LEAF>>) {
+    let start = Instant::now();
+    let nodes = sections.iter().map(|section| Node::new(*section)).collect();
+    (start.elapsed(), nodes)
+}
+
+fn lookup<T: PaletteIndex, const LEAF: usize>(nodes: &[Node<T, LEAF>]) -> Duration {
+    let start = Instant::now();
+    for node in nodes {
+        for i in 0..16 * 16 * 16 {
+            let pos = LocalBlockPosition::new(i & 0x0f, i >> 8, (i >> 4) & 0x0f);
+            std::hint::black_box(node.get(std::hint::black_box(pos)));
+        }
+    }
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::section_position;
+
+    fn check<const LEAF: usize>() {
+        // noisy bottom quarter, uniform above
+        let section: Section<u16> = std::array::from_fn(|i| if i < 16 * 16 * 4 { (i % 7) as u16 } else { 3 });
+        let mut node: Node<u16, LEAF> = Node::new(section);
+        assert!((0..16 * 16 * 16).all(|i| node.get(section_position(i)) == section[i]), "leaf {}", LEAF);
+        assert_eq!(node.leaves().map(|(_, size, _)| (size as usize).pow(3)).sum::<usize>(), 16 * 16 * 16, "leaf {}", LEAF);
+        assert!(node.leaves().all(|(pos, _, value)| node.get(pos) == value), "leaf {}", LEAF);
+
+        // setting a block back merges the tree into what `Node::new` builds
+        let json = serde_json::to_string(&node).unwrap();
+        node.set(section_position(4095), 9);
+        assert_eq!(node.get(section_position(4095)), 9, "leaf {}", LEAF);
+        node.set(section_position(4095), 3);
+        assert_eq!(serde_json::to_string(&node).unwrap(), json, "leaf {}", LEAF);
+
+        let decoded: Node<u16, LEAF> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_section(), section, "leaf {}", LEAF);
+    }
+
+    #[test]
+    fn every_leaf_size() {
+        check::<1>();
+        check::<2>();
+        check::<4>();
+        check::<8>();
+        check::<16>();
+    }
+}
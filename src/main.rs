@@ -1,565 +1,212 @@
-use std::collections::{HashSet, VecDeque};
 use std::convert::TryInto;
 use std::fmt::{Debug, Formatter};
-use std::io::{BufReader, Write};
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
-use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time::Instant;
-use bimap::{BiBTreeMap, BiMap};
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use elor::Either;
+use bimap::BiBTreeMap;
 use get_size::GetSize;
-use elor::Either::{Left, Right};
-use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
-use serde::{Serialize, Serializer};
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, GetSize, serde::Serialize, serde::Deserialize, PartialOrd, Ord, Hash)]
-#[repr(u16)]
-pub enum Block {
-    Air = 0,
-    Stone = 1,
-    Grass = 2,
-    Dirt = 3,
-    Cobblestone = 4,
-    Wood = 5,
-    Sapling = 6,
-    Bedrock = 7,
-    WaterSolid = 8,
-    Water = 9,
-    LavaSolid = 10,
-    Lava = 11,
-    Sand = 12,
-    Gravel = 13,
-    GoldOre = 14,
-    IronOre = 15,
-    CoalOre = 16,
-    Log = 17,
-    Leaves = 18,
-    Sponge = 19,
-    Glass = 20,
-    LapisOre = 21,
-    LapisBlock = 22,
-    Dispenser = 23,
-    Sandstone = 24,
-    NoteBlock = 25,
-    BedBlock = 26,
-    PoweredRail = 27,
-    DetectorRail = 28,
-    PistonStickyBase = 29,
-    Web = 30,
-    LongGrassBottom = 31,
-    LongGrassUp = 32,
-    PistonBase = 33,
-    PistonExtension = 34,
-    Wool = 35,
-    PistonMovingPiece = 36,
-    YellowFlower = 37,
-    RedRose = 38,
-    BrownMushroom = 39,
-    RedMushroom = 40,
-    GoldBlock = 41,
-    IronBlock = 42,
-    StepDown = 43,
-    StepUp = 44,
-    Brick = 45,
-    Tnt = 46,
-    Bookshelf = 47,
-    MossyCobblestone = 48,
-    Obsidian = 49,
-    Torch = 50,
-    Fire = 51,
-    MobSpawner = 52,
-    WoodStairs = 53,
-    Chest = 54,
-    RedstoneWire = 55,
-    DiamondOre = 56,
-    DiamondBlock = 57,
-    Workbench = 58,
-    Crops = 59,
-    Soil = 60,
-    FurnaceOff = 61,
-    FurnaceOn = 62,
-    SignPost = 63,
-    WoodenDoor = 64,
-    Ladder = 65,
-    Rails = 66,
-    CobblestoneStairs = 67,
-    WallSign = 68,
-    Lever = 69,
-    StonePlate = 70,
-    IronDoorBlock = 71,
-    WoodPlate = 72,
-    RedstoneOreOff = 73,
-    RedstoneOreOn = 74,
-    RedstoneTorchOff = 75,
-    RedstoneTorchOn = 76,
-    StoneButton = 77,
-    Snow = 78,
-    Ice = 79,
-    SnowBlock = 80,
-    Cactus = 81,
-    Clay = 82,
-    SugarCaneBlock = 83,
-    Jukebox = 84,
-    Fence = 85,
-    Pumpkin = 86,
-    Netherrack = 87,
-    SoulSand = 88,
-    Glowstone = 89,
-    Portal = 90,
-    JackOLantern = 91,
-    CakeBlock = 92,
-    DiodeBlockOff = 93,
-    DiodeBlockOn = 94,
-    StainedGlass = 95,
-    TrapDoor = 96,
-    MonsterEggs = 97,
-    SmoothBrick = 98,
-    HugeMushroom1 = 99,
-    HugeMushroom2 = 100,
-    IronFence = 101,
-    ThinGlass = 102,
-    MelonBlock = 103,
-    PumpkinStem = 104,
-    MelonStem = 105,
-    Vine = 106,
-    FenceGate = 107,
-    BrickStairs = 108,
-    SmoothStairs = 109,
-    Mycel = 110,
-    WaterLily = 111,
-    NetherBrick = 112,
-    NetherFence = 113,
-    NetherBrickStairs = 114,
-    NetherWarts = 115,
-    EnchantmentTable = 116,
-    BrewingStand = 117,
-    Cauldron = 118,
-    EnderPortal = 119,
-    EnderPortalFrame = 120,
-    EnderStone = 121,
-    DragonEgg = 122,
-    RedstoneLampOff = 123,
-    RedstoneLampOn = 124,
-    WoodStepDown = 125,
-    WoodStepUp = 126,
-    Cocoa = 127,
-    SandstoneStairs = 128,
-    EmeraldOre = 129,
-    EnderChest = 130,
-    TripwireHook = 131,
-    Tripwire = 132,
-    EmeraldBlock = 133,
-    SpruceWoodStairs = 134,
-    BirchWoodStairs = 135,
-    JungleWoodStairs = 136,
-    Command = 137,
-    Beacon = 138,
-    CobbleWall = 139,
-    FlowerPot = 140,
-    Carrot = 141,
-    Potato = 142,
-    WoodButton = 143,
-    Anvil = 145,
-    TrappedChest = 146,
-    GoldPlate = 147,
-    IronPlate = 148,
-    RedstoneComparatorOff = 149,
-    RedstoneComparatorOn = 150,
-    DaylightDetectorOff = 151,
-    RedstoneBlock = 152,
-    QuartzOre = 153,
-    Hopper = 154,
-    QuartzBlock = 155,
-    QuartzStairs = 156,
-    ActivatorRail = 157,
-    Dropper = 158,
-    StainedClay = 159,
-    StainedGlassPane = 160,
-    Leaves2 = 161,
-    Log2 = 162,
-    AcaciaStairs = 163,
-    DarkOakStairs = 164,
-    SlimeBlock = 165,
-    Barrier = 166,
-    IronTrapdoor = 167,
-    Prismarine = 168,
-    SeaLantern = 169,
-    HayBlock = 170,
-    Carpet = 171,
-    HardClay = 172,
-    CoalBlock = 173,
-    PackedIce = 174,
-    DoublePlant = 175,
-    StandingBanner = 176,
-    WallBanner = 177,
-    DaylightDetectorOn = 178,
-    RedSandstone = 179,
-    RedSandstoneStairs = 180,
-    StoneSlab2 = 181,
-    SpruceFenceGate = 183,
-    BirchFenceGate = 184,
-    JungleFenceGate = 185,
-    DarkOakFenceGate = 186,
-    AcaciaFenceGate = 187,
-    SpruceFence = 188,
-    BirchFence = 189,
-    JungleFence = 190,
-    DarkOakFence = 191,
-    AcaciaFence = 192,
-    SpruceDoor = 193,
-    BirchDoor = 194,
-    JungleDoor = 195,
-    AcaciaDoor = 196,
-    DarkOakDoor = 197,
-    EndRod = 198,
-    ChorusPlant = 199,
-    ChorusFlower = 200,
-    PurpurBlock = 201,
-    PurpurPillar = 202,
-    PurpurStairs = 203,
-    PurpurSlabBottom = 204,
-    PurpurSlabUp = 205,
-    EndBricks = 206,
-    BeetrootBlock = 207,
-    GrassPath = 208,
-    EndGateway = 209,
-    CommandRepeating = 210,
-    CommandChain = 211,
-    FrostedIce = 212,
-    Magma = 213,
-    NetherWartBlock = 214,
-    RedNetherBrick = 215,
-    BoneBlock = 216,
-    StructureVoid = 217,
-    Observer = 218,
-    WhiteShulkerBox = 219,
-    OrangeShulkerBox = 220,
-    MagentaShulkerBox = 221,
-    LightBlueShulkerBox = 222,
-    YellowShulkerBox = 223,
-    LimeShulkerBox = 224,
-    PinkShulkerBox = 225,
-    GrayShulkerBox = 226,
-    SilverShulkerBox = 227,
-    CyanShulkerBox = 228,
-    PurpleShulkerBox = 229,
-    BlueShulkerBox = 230,
-    BrownShulkerBox = 231,
-    GreenShulkerBox = 232,
-    RedShulkerBox = 233,
-    BlackShulkerBox = 234,
-    WhiteGlazedTerracotta = 235,
-    OrangeGlazedTerracotta = 236,
-    MagentaGlazedTerracotta = 237,
-    LightBlueGlazedTerracotta = 238,
-    YellowGlazedTerracotta = 239,
-    LimeGlazedTerracotta = 240,
-    PinkGlazedTerracotta = 241,
-    GrayGlazedTerracotta = 242,
-    SilverGlazedTerracotta = 243,
-    CyanGlazedTerracotta = 244,
-    PurpleGlazedTerracotta = 245,
-    BlueGlazedTerracotta = 246,
-    BrownGlazedTerracotta = 247,
-    GreenGlazedTerracotta = 248,
-    RedGlazedTerracotta = 249,
-    BlackGlazedTerracotta = 250,
-    Concrete = 251,
-    ConcretePowder = 252,
-    StructureBlock = 255,
-}
-
-impl Into<u16> for Block {
-    fn into(self) -> u16 {
-        match self {
-            Self::Air => 0,
-            Self::Stone => 1,
-            Self::Grass => 2,
-            Self::Dirt => 3,
-            Self::Cobblestone => 4,
-            Self::Wood => 5,
-            Self::Sapling => 6,
-            Self::Bedrock => 7,
-            Self::WaterSolid => 8,
-            Self::Water => 9,
-            Self::LavaSolid => 10,
-            Self::Lava => 11,
-            Self::Sand => 12,
-            Self::Gravel => 13,
-            Self::GoldOre => 14,
-            Self::IronOre => 15,
-            Self::CoalOre => 16,
-            Self::Log => 17,
-            Self::Leaves => 18,
-            Self::Sponge => 19,
-            Self::Glass => 20,
-            Self::LapisOre => 21,
-            Self::LapisBlock => 22,
-            Self::Dispenser => 23,
-            Self::Sandstone => 24,
-            Self::NoteBlock => 25,
-            Self::BedBlock => 26,
-            Self::PoweredRail => 27,
-            Self::DetectorRail => 28,
-            Self::PistonStickyBase => 29,
-            Self::Web => 30,
-            Self::LongGrassBottom => 31,
-            Self::LongGrassUp => 32,
-            Self::PistonBase => 33,
-            Self::PistonExtension => 34,
-            Self::Wool => 35,
-            Self::PistonMovingPiece => 36,
-            Self::YellowFlower => 37,
-            Self::RedRose => 38,
-            Self::BrownMushroom => 39,
-            Self::RedMushroom => 40,
-            Self::GoldBlock => 41,
-            Self::IronBlock => 42,
-            Self::StepDown => 43,
-            Self::StepUp => 44,
-            Self::Brick => 45,
-            Self::Tnt => 46,
-            Self::Bookshelf => 47,
-            Self::MossyCobblestone => 48,
-            Self::Obsidian => 49,
-            Self::Torch => 50,
-            Self::Fire => 51,
-            Self::MobSpawner => 52,
-            Self::WoodStairs => 53,
-            Self::Chest => 54,
-            Self::RedstoneWire => 55,
-            Self::DiamondOre => 56,
-            Self::DiamondBlock => 57,
-            Self::Workbench => 58,
-            Self::Crops => 59,
-            Self::Soil => 60,
-            Self::FurnaceOff => 61,
-            Self::FurnaceOn => 62,
-            Self::SignPost => 63,
-            Self::WoodenDoor => 64,
-            Self::Ladder => 65,
-            Self::Rails => 66,
-            Self::CobblestoneStairs => 67,
-            Self::WallSign => 68,
-            Self::Lever => 69,
-            Self::StonePlate => 70,
-            Self::IronDoorBlock => 71,
-            Self::WoodPlate => 72,
-            Self::RedstoneOreOff => 73,
-            Self::RedstoneOreOn => 74,
-            Self::RedstoneTorchOff => 75,
-            Self::RedstoneTorchOn => 76,
-            Self::StoneButton => 77,
-            Self::Snow => 78,
-            Self::Ice => 79,
-            Self::SnowBlock => 80,
-            Self::Cactus => 81,
-            Self::Clay => 82,
-            Self::SugarCaneBlock => 83,
-            Self::Jukebox => 84,
-            Self::Fence => 85,
-            Self::Pumpkin => 86,
-            Self::Netherrack => 87,
-            Self::SoulSand => 88,
-            Self::Glowstone => 89,
-            Self::Portal => 90,
-            Self::JackOLantern => 91,
-            Self::CakeBlock => 92,
-            Self::DiodeBlockOff => 93,
-            Self::DiodeBlockOn => 94,
-            Self::StainedGlass => 95,
-            Self::TrapDoor => 96,
-            Self::MonsterEggs => 97,
-            Self::SmoothBrick => 98,
-            Self::HugeMushroom1 => 99,
-            Self::HugeMushroom2 => 100,
-            Self::IronFence => 101,
-            Self::ThinGlass => 102,
-            Self::MelonBlock => 103,
-            Self::PumpkinStem => 104,
-            Self::MelonStem => 105,
-            Self::Vine => 106,
-            Self::FenceGate => 107,
-            Self::BrickStairs => 108,
-            Self::SmoothStairs => 109,
-            Self::Mycel => 110,
-            Self::WaterLily => 111,
-            Self::NetherBrick => 112,
-            Self::NetherFence => 113,
-            Self::NetherBrickStairs => 114,
-            Self::NetherWarts => 115,
-            Self::EnchantmentTable => 116,
-            Self::BrewingStand => 117,
-            Self::Cauldron => 118,
-            Self::EnderPortal => 119,
-            Self::EnderPortalFrame => 120,
-            Self::EnderStone => 121,
-            Self::DragonEgg => 122,
-            Self::RedstoneLampOff => 123,
-            Self::RedstoneLampOn => 124,
-            Self::WoodStepDown => 125,
-            Self::WoodStepUp => 126,
-            Self::Cocoa => 127,
-            Self::SandstoneStairs => 128,
-            Self::EmeraldOre => 129,
-            Self::EnderChest => 130,
-            Self::TripwireHook => 131,
-            Self::Tripwire => 132,
-            Self::EmeraldBlock => 133,
-            Self::SpruceWoodStairs => 134,
-            Self::BirchWoodStairs => 135,
-            Self::JungleWoodStairs => 136,
-            Self::Command => 137,
-            Self::Beacon => 138,
-            Self::CobbleWall => 139,
-            Self::FlowerPot => 140,
-            Self::Carrot => 141,
-            Self::Potato => 142,
-            Self::WoodButton => 143,
-            Self::Anvil => 145,
-            Self::TrappedChest => 146,
-            Self::GoldPlate => 147,
-            Self::IronPlate => 148,
-            Self::RedstoneComparatorOff => 149,
-            Self::RedstoneComparatorOn => 150,
-            Self::DaylightDetectorOff => 151,
-            Self::RedstoneBlock => 152,
-            Self::QuartzOre => 153,
-            Self::Hopper => 154,
-            Self::QuartzBlock => 155,
-            Self::QuartzStairs => 156,
-            Self::ActivatorRail => 157,
-            Self::Dropper => 158,
-            Self::StainedClay => 159,
-            Self::StainedGlassPane => 160,
-            Self::Leaves2 => 161,
-            Self::Log2 => 162,
-            Self::AcaciaStairs => 163,
-            Self::DarkOakStairs => 164,
-            Self::SlimeBlock => 165,
-            Self::Barrier => 166,
-            Self::IronTrapdoor => 167,
-            Self::Prismarine => 168,
-            Self::SeaLantern => 169,
-            Self::HayBlock => 170,
-            Self::Carpet => 171,
-            Self::HardClay => 172,
-            Self::CoalBlock => 173,
-            Self::PackedIce => 174,
-            Self::DoublePlant => 175,
-            Self::StandingBanner => 176,
-            Self::WallBanner => 177,
-            Self::DaylightDetectorOn => 178,
-            Self::RedSandstone => 179,
-            Self::RedSandstoneStairs => 180,
-            Self::StoneSlab2 => 181,
-            Self::SpruceFenceGate => 183,
-            Self::BirchFenceGate => 184,
-            Self::JungleFenceGate => 185,
-            Self::DarkOakFenceGate => 186,
-            Self::AcaciaFenceGate => 187,
-            Self::SpruceFence => 188,
-            Self::BirchFence => 189,
-            Self::JungleFence => 190,
-            Self::DarkOakFence => 191,
-            Self::AcaciaFence => 192,
-            Self::SpruceDoor => 193,
-            Self::BirchDoor => 194,
-            Self::JungleDoor => 195,
-            Self::AcaciaDoor => 196,
-            Self::DarkOakDoor => 197,
-            Self::EndRod => 198,
-            Self::ChorusPlant => 199,
-            Self::ChorusFlower => 200,
-            Self::PurpurBlock => 201,
-            Self::PurpurPillar => 202,
-            Self::PurpurStairs => 203,
-            Self::PurpurSlabBottom => 204,
-            Self::PurpurSlabUp => 205,
-            Self::EndBricks => 206,
-            Self::BeetrootBlock => 207,
-            Self::GrassPath => 208,
-            Self::EndGateway => 209,
-            Self::CommandRepeating => 210,
-            Self::CommandChain => 211,
-            Self::FrostedIce => 212,
-            Self::Magma => 213,
-            Self::NetherWartBlock => 214,
-            Self::RedNetherBrick => 215,
-            Self::BoneBlock => 216,
-            Self::StructureVoid => 217,
-            Self::Observer => 218,
-            Self::WhiteShulkerBox => 219,
-            Self::OrangeShulkerBox => 220,
-            Self::MagentaShulkerBox => 221,
-            Self::LightBlueShulkerBox => 222,
-            Self::YellowShulkerBox => 223,
-            Self::LimeShulkerBox => 224,
-            Self::PinkShulkerBox => 225,
-            Self::GrayShulkerBox => 226,
-            Self::SilverShulkerBox => 227,
-            Self::CyanShulkerBox => 228,
-            Self::PurpleShulkerBox => 229,
-            Self::BlueShulkerBox => 230,
-            Self::BrownShulkerBox => 231,
-            Self::GreenShulkerBox => 232,
-            Self::RedShulkerBox => 233,
-            Self::BlackShulkerBox => 234,
-            Self::WhiteGlazedTerracotta => 235,
-            Self::OrangeGlazedTerracotta => 236,
-            Self::MagentaGlazedTerracotta => 237,
-            Self::LightBlueGlazedTerracotta => 238,
-            Self::YellowGlazedTerracotta => 239,
-            Self::LimeGlazedTerracotta => 240,
-            Self::PinkGlazedTerracotta => 241,
-            Self::GrayGlazedTerracotta => 242,
-            Self::SilverGlazedTerracotta => 243,
-            Self::CyanGlazedTerracotta => 244,
-            Self::PurpleGlazedTerracotta => 245,
-            Self::BlueGlazedTerracotta => 246,
-            Self::BrownGlazedTerracotta => 247,
-            Self::GreenGlazedTerracotta => 248,
-            Self::RedGlazedTerracotta => 249,
-            Self::BlackGlazedTerracotta => 250,
-            Self::Concrete => 251,
-            Self::ConcretePowder => 252,
-            Self::StructureBlock => 255,
-        }
-    }
-}
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+// Modules with `allow(dead_code)` are APIs that only the tests call so far.
+#[allow(dead_code)]
+mod anvil;
+mod block;
+mod codec;
+mod dag;
+mod dat;
+mod encoding;
+mod flattening;
+#[allow(dead_code)]
+mod lazy_world;
+mod leaf_size;
+mod mmap;
+mod nbt;
+#[allow(dead_code)]
+mod pack;
+mod packed;
+mod properties;
+#[allow(dead_code)]
+mod registry;
+#[allow(dead_code)]
+mod schematic;
+#[allow(dead_code)]
+mod sponge;
+#[allow(dead_code)]
+mod volume;
+mod world;
+
+use block::{Block, BlockState};
+use dat::DatFormat;
+use packed::SectionStorage;
+use world::World;
 
 type Section<T> = [T; 16 * 16 * 16];
 type Sections<T> = [Section<T>; 24];
-type RawSection = [Block; 16 * 16 * 16];
-type RawResctions = [RawSection; 24];
+type RawSection = [BlockState; 16 * 16 * 16];
+type RawSections = [RawSection; 24];
 
+/// Default edge length of a `Blocks` leaf, see [`Node`].
 const MINIMAL_NODE_SIZE: usize = 4;
 
-trait PaletteIndex: Into<usize> + Copy + PartialEq + Eq + Ord + Debug + Send + Sync {}
+trait PaletteIndex: Into<usize> + TryFrom<usize> + Copy + Default + PartialEq + Eq + Ord + Debug + GetSize + Send + Sync {}
 
 impl PaletteIndex for u16 {}
 
 impl PaletteIndex for u8 {}
 
 
-#[derive(Clone, Debug)]
-enum Node<T> where T: PaletteIndex {
+/// `LEAF` is the edge length of the `Blocks` leaves, a power of two of at most 16. Sections are
+/// always 16 blocks wide, like the chunks they come from.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+enum Node<T, const LEAF: usize = { MINIMAL_NODE_SIZE }> where T: PaletteIndex {
     Block(T),
-    Blocks(Box<[T; MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE]>),
-    Nodes(Box<[Node<T>; 8]>),
+    Blocks(#[serde(with = "leaf")] Box<Leaf<T, LEAF>>),
+    Nodes(Box<[Node<T, LEAF>; 8]>),
+}
+
+/// A `Blocks` leaf of `L`³ cells, indexed `[y][z][x]` so its memory is in yzx order.
+type Leaf<T, const L: usize> = [[[T; L]; L]; L];
+
+/// The cells of a leaf in yzx order.
+fn leaf_cells<T, const L: usize>(leaf: &Leaf<T, L>) -> &[T] {
+    leaf.as_flattened().as_flattened()
 }
 
+/// Inverse of [`leaf_cells`], `None` unless `cells` holds exactly `L`³ values.
+fn leaf_from_cells<T: Copy, const L: usize>(cells: &[T]) -> Option<Box<Leaf<T, L>>> {
+    (cells.len() == L * L * L).then(|| Box::new(std::array::from_fn(|y| std::array::from_fn(|z| std::array::from_fn(|x| cells[x + z * L + y * L * L])))))
+}
+
+/// serde only implements arrays of up to 32 elements, so leaves go through a sequence.
+mod leaf {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error;
 
-#[derive(Debug)]
-enum Nodes {
-    Large(Box<[Node<u16>; 24]>, BiBTreeMap<u16, Block>),
-    Small(Box<[Node<u8>; 24]>, BiBTreeMap<u8, Block>),
+    use crate::{Leaf, leaf_cells, leaf_from_cells};
+
+    pub fn serialize<S: Serializer, T: Serialize, const L: usize>(blocks: &Leaf<T, L>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(leaf_cells(blocks))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de> + Copy, const L: usize>(deserializer: D) -> Result<Box<Leaf<T, L>>, D::Error> {
+        let blocks = Vec::<T>::deserialize(deserializer)?;
+        leaf_from_cells(&blocks).ok_or_else(|| D::Error::invalid_length(blocks.len(), &"a full leaf of blocks"))
+    }
 }
 
-impl<T: PaletteIndex> Node<T> {
+// get_size's derive does not handle generics
+impl<T: PaletteIndex, const LEAF: usize> GetSize for Node<T, LEAF> {
+    fn get_heap_size(&self) -> usize {
+        match self {
+            Self::Block(_) => 0,
+            Self::Blocks(blocks) => blocks.get_size(),
+            Self::Nodes(children) => children.get_size(),
+        }
+    }
+}
+
+
+/// Iterator of [`Node::leaves`], depth first in child order.
+struct Leaves<'a, T: PaletteIndex, const LEAF: usize> {
+    stack: Vec<(&'a Node<T, LEAF>, [u8; 3], u8)>,
+    /// The `Blocks` leaf being yielded, its origin and the next cell.
+    cells: Option<(&'a [T], [u8; 3], usize)>,
+}
+
+impl<T: PaletteIndex, const LEAF: usize> Iterator for Leaves<'_, T, LEAF> {
+    type Item = (LocalBlockPosition, u8, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((blocks, [x, y, z], i)) = self.cells {
+            self.cells = (i + 1 < blocks.len()).then_some((blocks, [x, y, z], i + 1));
+            let (dx, dz, dy) = (i % LEAF, (i / LEAF) % LEAF, i / (LEAF * LEAF));
+            let pos = LocalBlockPosition::new((x as usize + dx) as u32, (y as usize + dy) as u32, (z as usize + dz) as u32);
+            return Some((pos, 1, blocks[i]));
+        }
+
+        loop {
+            let (node, [x, y, z], size) = self.stack.pop()?;
+            match node {
+                Node::Block(id) => return Some((LocalBlockPosition::new(x as u32, y as u32, z as u32), size, *id)),
+                Node::Blocks(blocks) => {
+                    self.cells = Some((leaf_cells(blocks), [x, y, z], 0));
+                    return self.next();
+                }
+                Node::Nodes(children) => {
+                    let size = size / 2;
+                    self.stack.extend(children.iter().enumerate().rev().map(|(i, child)| {
+                        (child, [x + (i & 1) as u8 * size, y + (i >> 2) as u8 * size, z + ((i >> 1) & 1) as u8 * size], size)
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// How [`Node::paint_internal`] changes the blocks of its box.
+#[derive(Copy, Clone)]
+enum Paint<T> {
+    Fill(T),
+    Replace { from: T, to: T },
+}
+
+impl<T: PaletteIndex> Paint<T> {
+    fn apply(self, value: T) -> T {
+        match self {
+            Self::Fill(value) => value,
+            Self::Replace { from, to } if value == from => to,
+            Self::Replace { .. } => value,
+        }
+    }
+}
+
+/// Position of the `i`-th block of a section in yzx order.
+fn section_position(i: usize) -> LocalBlockPosition {
+    LocalBlockPosition::new(i as u32 & 0x0f, i as u32 >> 8, (i as u32 >> 4) & 0x0f)
+}
+
+/// Inclusive xyz bounds of the box between two section-relative corners, in either order.
+fn section_box(a: LocalBlockPosition, b: LocalBlockPosition) -> ([u8; 3], [u8; 3]) {
+    let xyz = |p: LocalBlockPosition| [(p.c >> 8) & 0x0f, (p.c >> 16) & 0x0f, p.c & 0x0f].map(|v| v as u8);
+    let (a, b) = (xyz(a), xyz(b));
+    (std::array::from_fn(|i| a[i].min(b[i])), std::array::from_fn(|i| a[i].max(b[i])))
+}
+
+/// `S` is what the palette maps indices to, legacy states unless stated otherwise.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "UncheckedNodes<S>")]
+enum Nodes<S: Ord = BlockState> {
+    Large(Box<[Node<u16>; 24]>, BiBTreeMap<u16, S>),
+    Small(Box<[Node<u8>; 24]>, BiBTreeMap<u8, S>),
+}
+
+/// [`Nodes`] as serde reads it, before the trees are checked against their sizes and palette.
+#[derive(serde::Deserialize)]
+#[serde(rename = "Nodes")]
+enum UncheckedNodes<S: Ord> {
+    Large(Box<[Node<u16>; 24]>, BiBTreeMap<u16, S>),
+    Small(Box<[Node<u8>; 24]>, BiBTreeMap<u8, S>),
+}
+
+impl<S: Ord> TryFrom<UncheckedNodes<S>> for Nodes<S> {
+    type Error = encoding::DecodeError;
+
+    fn try_from(nodes: UncheckedNodes<S>) -> Result<Self, Self::Error> {
+        fn check<T: PaletteIndex, S: Ord>(nodes: &[Node<T>; 24], blocks: &BiBTreeMap<T, S>) -> Result<(), encoding::DecodeError> {
+            nodes.iter().try_for_each(|node| encoding::check_node(node, blocks, 16))
+        }
+
+        match nodes {
+            UncheckedNodes::Large(nodes, blocks) => check(&nodes, &blocks).map(|()| Nodes::Large(nodes, blocks)),
+            UncheckedNodes::Small(nodes, blocks) => check(&nodes, &blocks).map(|()| Nodes::Small(nodes, blocks)),
+        }
+    }
+}
+
+impl<T: PaletteIndex, const LEAF: usize> Node<T, LEAF> {
+    const VALID_LEAF: () = assert!(LEAF.is_power_of_two() && LEAF <= 16, "leaves are a power of two of at most 16 wide");
+
     pub fn new(section: Section<T>) -> Self {
+        let () = Self::VALID_LEAF;
         Self::new_internal(section, 0, 0, 0, 16)
     }
 
@@ -568,6 +215,47 @@ impl<T: PaletteIndex> Node<T> {
         self.get_block_internal(x as u8, y as u8, z as u8, 16)
     }
 
+    /// Every block of the section with its position, in yzx order. The tree is walked once into a
+    /// flat section rather than once per block like repeated [`Node::get`] calls.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item=(LocalBlockPosition, T)> {
+        let section = self.to_section();
+        (0..16 * 16 * 16).map(move |i| (section_position(i), section[i]))
+    }
+
+    /// Every uniform cube of the tree as `(origin, size, value)`: one per `Block` node, and one of
+    /// size 1 per cell of a `Blocks` leaf. Together they cover the section exactly once.
+    pub fn leaves(&self) -> Leaves<'_, T, LEAF> {
+        Leaves { stack: vec![(self, [0; 3], 16)], cells: None }
+    }
+
+    /// Expands the node back into a flat section in yzx order.
+    fn to_section(&self) -> Section<T> {
+        let mut section = [T::default(); 16 * 16 * 16];
+        self.fill_internal(&mut section, 0, 0, 0, 16);
+        section
+    }
+
+    fn fill_internal(&self, section: &mut Section<T>, x: usize, y: usize, z: usize, size: usize) {
+        match self {
+            Self::Block(id) => for y in y..y + size {
+                for z in z..z + size {
+                    section[x + (z << 4) + (y << 8)..(size + x) + (z << 4) + (y << 8)].fill(*id);
+                }
+            },
+            Self::Blocks(blocks) => for (i, row) in blocks.as_flattened().iter().enumerate() {
+                let (z, y) = (z + i % LEAF, y + i / LEAF);
+                section[x + (z << 4) + (y << 8)..(size + x) + (z << 4) + (y << 8)].copy_from_slice(row);
+            },
+            Self::Nodes(children) => {
+                let size = size / 2;
+                for (i, child) in children.iter().enumerate() {
+                    child.fill_internal(section, x + (i & 1) * size, y + (i >> 2) * size, z + ((i >> 1) & 1) * size, size);
+                }
+            }
+        }
+    }
+
     fn new_internal(section: Section<T>, x: usize, y: usize, z: usize, size: usize) -> Self {
         let s = section.as_ref();
         let blocks: Vec<T> = (y..y + size).flat_map(|y| (z..z + size)
@@ -578,22 +266,144 @@ impl<T: PaletteIndex> Node<T> {
             return Self::Block(first);
         }
 
-        if size == MINIMAL_NODE_SIZE {
-            return Self::Blocks(blocks.try_into().unwrap());
+        if size == LEAF {
+            return Self::Blocks(leaf_from_cells(&blocks).unwrap());
         }
 
         let size = size / 2;
-        let children: [Node<T>; 8] = std::array::from_fn(|i|
+        let children: [Node<T, LEAF>; 8] = std::array::from_fn(|i|
             Self::new_internal(section, x + (i & 1) * size, y + (i >> 2) * size, z + ((i >> 1) & 1) * size, size)
         );
 
         Self::Nodes(children.into())
     }
 
+    /// Splits uniform nodes on the way down to `pos` and merges the ones the change makes uniform,
+    /// so the tree stays as small as [`Node::new`] would build it.
+    #[allow(dead_code)]
+    pub fn set(&mut self, pos: LocalBlockPosition, value: T) {
+        let (x, y, z) = ((pos.c >> 8) & 0x0f, pos.c >> 16, pos.c & 0x0f);
+        self.set_internal(x as u8, y as u8, z as u8, 16, value)
+    }
+
+    fn set_internal(&mut self, x: u8, y: u8, z: u8, size: u8, value: T) {
+        if let Self::Block(id) = *self {
+            if id == value {
+                return;
+            }
+            *self = if size as usize == LEAF {
+                Self::Blocks(Box::new([[[id; LEAF]; LEAF]; LEAF]))
+            } else {
+                Self::Nodes(Box::new(std::array::from_fn(|_| Self::Block(id))))
+            };
+        }
+
+        let uniform = match self {
+            Self::Block(_) => unreachable!(),
+            Self::Blocks(blocks) => {
+                blocks[y as usize][z as usize][x as usize] = value;
+                leaf_cells(blocks).iter().all(|b| *b == value)
+            }
+            Self::Nodes(children) => {
+                let child_size = size / 2;
+                let child_index = (x / child_size) + ((y / child_size) << 2) + ((z / child_size) << 1);
+                children[child_index as usize].set_internal(x % child_size, y % child_size, z % child_size, child_size, value);
+                children.iter().all(|c| matches!(c, Self::Block(id) if *id == value))
+            }
+        };
+        if uniform {
+            *self = Self::Block(value);
+        }
+    }
+
+    /// Sets every block of the inclusive box between `min` and `max`. Octants the box covers become
+    /// a single `Block` without visiting their children.
+    pub fn fill(&mut self, min: LocalBlockPosition, max: LocalBlockPosition, value: T) {
+        let (lo, hi) = section_box(min, max);
+        self.paint_internal(lo, hi, 16, Paint::Fill(value))
+    }
+
+    /// Turns every `from` in the inclusive box between `min` and `max` into `to`.
+    #[allow(dead_code)]
+    pub fn replace(&mut self, min: LocalBlockPosition, max: LocalBlockPosition, from: T, to: T) {
+        let (lo, hi) = section_box(min, max);
+        self.paint_internal(lo, hi, 16, Paint::Replace { from, to })
+    }
+
+    /// `lo` and `hi` are inclusive xyz bounds relative to this node, already clipped to it.
+    fn paint_internal(&mut self, lo: [u8; 3], hi: [u8; 3], size: u8, paint: Paint<T>) {
+        let covered = lo == [0; 3] && hi == [size - 1; 3];
+        match (&*self, paint) {
+            (Self::Block(id), _) if paint.apply(*id) == *id => return,
+            (_, Paint::Fill(value)) if covered => {
+                *self = Self::Block(value);
+                return;
+            }
+            (Self::Block(id), _) if covered => {
+                *self = Self::Block(paint.apply(*id));
+                return;
+            }
+            (Self::Block(id), _) => {
+                let id = *id;
+                *self = if size as usize == LEAF {
+                    Self::Blocks(Box::new([[[id; LEAF]; LEAF]; LEAF]))
+                } else {
+                    Self::Nodes(Box::new(std::array::from_fn(|_| Self::Block(id))))
+                };
+            }
+            _ => {}
+        }
+
+        let uniform = match self {
+            Self::Block(_) => unreachable!(),
+            Self::Blocks(blocks) => {
+                for y in lo[1]..=hi[1] {
+                    for z in lo[2]..=hi[2] {
+                        for x in lo[0]..=hi[0] {
+                            let cell = &mut blocks[y as usize][z as usize][x as usize];
+                            *cell = paint.apply(*cell);
+                        }
+                    }
+                }
+                let cells = leaf_cells(blocks);
+                Some(cells[0]).filter(|first| cells.iter().all(|b| b == first))
+            }
+            Self::Nodes(children) => {
+                let child_size = size / 2;
+                for (i, child) in children.iter_mut().enumerate() {
+                    let offset = [(i & 1) as u8, (i >> 2) as u8, ((i >> 1) & 1) as u8].map(|o| o * child_size);
+                    if (0..3).any(|a| lo[a] >= offset[a] + child_size || hi[a] < offset[a]) {
+                        continue;
+                    }
+                    let child_lo = std::array::from_fn(|a| lo[a].max(offset[a]) - offset[a]);
+                    let child_hi = std::array::from_fn(|a| hi[a].min(offset[a] + child_size - 1) - offset[a]);
+                    child.paint_internal(child_lo, child_hi, child_size, paint);
+                }
+                match &children[0] {
+                    Self::Block(first) => Some(*first).filter(|first| children.iter().all(|c| matches!(c, Self::Block(id) if id == first))),
+                    _ => None,
+                }
+            }
+        };
+        if let Some(value) = uniform {
+            *self = Self::Block(value);
+        }
+    }
+
+    /// The same tree with every value passed through `f`, which must be injective to keep nodes
+    /// from becoming uniform without being merged.
+    fn map<U: PaletteIndex>(&self, f: impl Fn(T) -> U + Copy) -> Node<U, LEAF> {
+        match self {
+            Self::Block(id) => Node::Block(f(*id)),
+            Self::Blocks(blocks) => Node::Blocks(Box::new(blocks.map(|plane| plane.map(|row| row.map(f))))),
+            Self::Nodes(children) => Node::Nodes(Box::new(children.each_ref().map(|c| c.map(f)))),
+        }
+    }
+
     fn get_block_internal(&self, x: u8, y: u8, z: u8, size: u8) -> T {
         match self {
             Self::Block(id) => *id,
-            Self::Blocks(blocks) => blocks[x as usize + z as usize * MINIMAL_NODE_SIZE + y as usize * MINIMAL_NODE_SIZE * MINIMAL_NODE_SIZE],
+            Self::Blocks(blocks) => blocks[y as usize][z as usize][x as usize],
             Self::Nodes(children) => {
                 let child_size = size / 2;
                 let child_index = (x / child_size) + ((y / child_size) << 2) + ((z / child_size) << 1);
@@ -604,30 +414,29 @@ impl<T: PaletteIndex> Node<T> {
 }
 
 
-#[derive(Debug)]
-struct ChunkData {
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ChunkData<S: Ord = BlockState> {
     // down -> top
-    pub nodes: Nodes,
+    pub nodes: Nodes<S>,
 }
 
-impl From<&RawResctions> for ChunkData {
-    fn from(raw: &RawResctions) -> Self {
+impl<S: Clone + Ord> From<&Sections<S>> for ChunkData<S> {
+    fn from(raw: &Sections<S>) -> Self {
         let mut blocks = vec![];
-        raw.iter().flatten().for_each(|b| if !blocks.contains(b) { blocks.push(*b) });
+        raw.iter().flatten().for_each(|b| if !blocks.contains(b) { blocks.push(b.clone()) });
 
-        fn convert<T: PaletteIndex>(raw: &[[Block; 16 * 16 * 16]; 24], blocks: &BiBTreeMap<T, Block>) -> Box<[Node<T>; 24]> {
-            let ids: Box<[T]> = raw.iter().flatten().map(|f| *blocks.get_by_right(f).unwrap()).collect();
-            let sections: &Sections<T> = bytemuck::cast_ref(ids.as_ref());
-
-            let nodes: Box<[Node<T>]> = sections.into_iter().map(|f| Node::new(f)).collect();
-            unsafe { std::mem::transmute::<_, Box<[Node<T>; 24]>>(nodes.as_ptr()) }
+        fn convert<T: PaletteIndex, S: Ord>(raw: &Sections<S>, blocks: &BiBTreeMap<T, S>) -> Box<[Node<T>; 24]> {
+            let nodes: Box<[Node<T>]> = raw.iter()
+                .map(|section| Node::new(section.each_ref().map(|b| *blocks.get_by_right(b).unwrap())))
+                .collect();
+            nodes.try_into().unwrap()
         }
 
         let nodes = if blocks.len() > u8::MAX as usize {
-            let blocks = blocks.into_iter().enumerate().map(|f| (f.0 as u16, f.1)).collect::<BiBTreeMap<u16, Block>>();
+            let blocks = blocks.into_iter().enumerate().map(|f| (f.0 as u16, f.1)).collect::<BiBTreeMap<u16, S>>();
             Nodes::Large(convert(raw, &blocks), blocks)
         } else {
-            let blocks = blocks.into_iter().enumerate().map(|f| (f.0 as u8, f.1)).collect::<BiBTreeMap<u8, Block>>();
+            let blocks = blocks.into_iter().enumerate().map(|f| (f.0 as u8, f.1)).collect::<BiBTreeMap<u8, S>>();
             Nodes::Small(convert(raw, &blocks), blocks)
         };
 
@@ -636,14 +445,214 @@ impl From<&RawResctions> for ChunkData {
 }
 
 
-#[derive(Default)]
+/// Index of `block`, added to the palette if it is new. The caller makes sure there is room.
+fn palette_index<T: PaletteIndex, S: Ord>(blocks: &mut BiBTreeMap<T, S>, block: S) -> T {
+    if let Some(index) = blocks.get_by_right(&block) {
+        return *index;
+    }
+    let index = free_index(blocks).expect("palette has room");
+    blocks.insert(index, block);
+    index
+}
+
+/// An index the palette does not use yet, past the highest one if possible. Decoded palettes may
+/// have gaps, so the length is not necessarily free.
+fn free_index<T: PaletteIndex, S: Ord>(blocks: &BiBTreeMap<T, S>) -> Option<T> {
+    let next = blocks.left_values().next_back().map_or(Some(0), |i| (*i).into().checked_add(1));
+    next.and_then(|i| T::try_from(i).ok())
+        .or_else(|| (0..).map_while(|i| T::try_from(i).ok()).find(|i| !blocks.contains_left(i)))
+}
+
+/// Splits the inclusive box between two chunk-relative corners into one box per section it spans.
+fn section_boxes(a: LocalBlockPosition, b: LocalBlockPosition) -> impl Iterator<Item=(usize, LocalBlockPosition, LocalBlockPosition)> {
+    let (bottom, top) = ((a.c >> 16).min(b.c >> 16), (a.c >> 16).max(b.c >> 16).min(24 * 16 - 1));
+    let (a, b) = (a.c & 0xffff, b.c & 0xffff);
+    (bottom >> 4..=top >> 4).map(move |section| {
+        let (y0, y1) = (bottom.max(section << 4) & 0x0f, top.min((section << 4) + 15) & 0x0f);
+        (section as usize, LocalBlockPosition { c: y0 << 16 | a }, LocalBlockPosition { c: y1 << 16 | b })
+    })
+}
+
+impl ChunkData {
+    /// A chunk of nothing but air.
+    pub fn empty() -> Self {
+        let nodes = Box::new(std::array::from_fn(|_| Node::Block(0)));
+        ChunkData { nodes: Nodes::Small(nodes, BiBTreeMap::from_iter([(0, BlockState::AIR)])) }
+    }
+}
+
+impl<S: Clone + Ord> ChunkData<S> {
+    /// Expands every section back into raw blocks, undoing `From<&Sections<S>>`.
+    pub fn to_raw_sections(&self) -> Box<Sections<S>> {
+        let sections: Box<[Section<S>]> = (0..24).map(|section| self.to_raw_section(section)).collect();
+        sections.try_into().unwrap_or_else(|_| unreachable!())
+    }
+
+    /// Expands one section, bottom first, into raw blocks in yzx order.
+    pub fn to_raw_section(&self, section: usize) -> Section<S> {
+        fn expand<T: PaletteIndex, S: Clone + Ord>(node: &Node<T>, blocks: &BiBTreeMap<T, S>) -> Section<S> {
+            node.to_section().map(|id| blocks.get_by_left(&id).unwrap().clone())
+        }
+
+        match &self.nodes {
+            Nodes::Large(nodes, blocks) => expand(&nodes[section], blocks),
+            Nodes::Small(nodes, blocks) => expand(&nodes[section], blocks),
+        }
+    }
+
+    /// Adds `block` to the palette if it is new, switching to 16-bit indices once 256 entries no
+    /// longer suffice. Entries that are no longer used stay in the palette.
+    ///
+    /// # Panics
+    /// If `pos.y` is at or above the 384 blocks of the chunk.
+    #[allow(dead_code)]
+    pub fn set_block(&mut self, pos: LocalBlockPosition, block: S) {
+        assert!(pos.c >> 16 < 24 * 16, "y {} is above the top of the chunk", pos.c >> 16);
+        self.reserve(&block);
+
+        fn set<T: PaletteIndex, S: Ord>(nodes: &mut [Node<T>; 24], blocks: &mut BiBTreeMap<T, S>, pos: LocalBlockPosition, block: S) {
+            let index = palette_index(blocks, block);
+            nodes[(pos.c >> 20) as usize].set(LocalBlockPosition { c: pos.c & 0x000f_ffff }, index);
+        }
+
+        match &mut self.nodes {
+            Nodes::Large(nodes, blocks) => set(nodes, blocks, pos, block),
+            Nodes::Small(nodes, blocks) => set(nodes, blocks, pos, block),
+        }
+    }
+
+    /// Sets every block of the inclusive box between `min` and `max`, corners in any order. Works
+    /// on whole octants where it can, see [`Node::fill`].
+    pub fn fill(&mut self, min: LocalBlockPosition, max: LocalBlockPosition, block: S) {
+        self.reserve(&block);
+
+        fn fill<T: PaletteIndex, S: Ord>(nodes: &mut [Node<T>; 24], blocks: &mut BiBTreeMap<T, S>, min: LocalBlockPosition, max: LocalBlockPosition, block: S) {
+            let index = palette_index(blocks, block);
+            for (section, min, max) in section_boxes(min, max) {
+                nodes[section].fill(min, max, index);
+            }
+        }
+
+        match &mut self.nodes {
+            Nodes::Large(nodes, blocks) => fill(nodes, blocks, min, max, block),
+            Nodes::Small(nodes, blocks) => fill(nodes, blocks, min, max, block),
+        }
+    }
+
+    /// Turns every `from` in the inclusive box between `min` and `max` into `to`, see
+    /// [`ChunkData::fill`].
+    #[allow(dead_code)]
+    pub fn replace(&mut self, min: LocalBlockPosition, max: LocalBlockPosition, from: &S, to: S) {
+        if !self.contains(from) {
+            return;
+        }
+        self.reserve(&to);
+
+        fn replace<T: PaletteIndex, S: Ord>(nodes: &mut [Node<T>; 24], blocks: &mut BiBTreeMap<T, S>, min: LocalBlockPosition, max: LocalBlockPosition, from: &S, to: S) {
+            let from = *blocks.get_by_right(from).unwrap();
+            let to = palette_index(blocks, to);
+            for (section, min, max) in section_boxes(min, max) {
+                nodes[section].replace(min, max, from, to);
+            }
+        }
+
+        match &mut self.nodes {
+            Nodes::Large(nodes, blocks) => replace(nodes, blocks, min, max, from, to),
+            Nodes::Small(nodes, blocks) => replace(nodes, blocks, min, max, from, to),
+        }
+    }
+
+    fn contains(&self, block: &S) -> bool {
+        match &self.nodes {
+            Nodes::Large(_, blocks) => blocks.contains_right(block),
+            Nodes::Small(_, blocks) => blocks.contains_right(block),
+        }
+    }
+
+    /// Switches to 16-bit indices if `block` is new and the 8-bit palette is full.
+    fn reserve(&mut self, block: &S) {
+        if let Nodes::Small(nodes, blocks) = &mut self.nodes {
+            if !blocks.contains_right(block) && free_index(blocks).is_none() {
+                let nodes = Box::new(nodes.each_ref().map(|node| node.map(u16::from)));
+                let blocks = std::mem::take(blocks).into_iter().map(|(i, b)| (i as u16, b)).collect();
+                self.nodes = Nodes::Large(nodes, blocks);
+            }
+        }
+    }
+
+    /// Every block of the chunk in yzx order, bottom section first. Positions count `y` from the
+    /// bottom of the chunk, like [`ChunkData::get`].
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item=(LocalBlockPosition, S)> + '_ {
+        let palette = Arc::new(self.palette());
+        (0..24).flat_map(move |section| self.section_blocks(section, palette.clone()))
+    }
+
+    /// [`ChunkData::iter`] with one rayon task per section. Blocks of a section stay in order.
+    #[allow(dead_code)]
+    pub fn par_iter(&self) -> impl ParallelIterator<Item=(LocalBlockPosition, S)> + '_ where S: Send + Sync {
+        let palette = Arc::new(self.palette());
+        (0..24).into_par_iter().flat_map_iter(move |section| self.section_blocks(section, palette.clone()))
+    }
+
+    /// Blocks by palette index. Gaps between indices hold an arbitrary block, no node refers to them.
+    fn palette(&self) -> Vec<S> {
+        fn by_index<T: PaletteIndex, S: Clone + Ord>(blocks: &BiBTreeMap<T, S>) -> Vec<S> {
+            let mut palette: Vec<S> = vec![];
+            for (index, block) in blocks {
+                palette.resize((*index).into(), block.clone());
+                palette.push(block.clone());
+            }
+            palette
+        }
+
+        match &self.nodes {
+            Nodes::Large(_, blocks) => by_index(blocks),
+            Nodes::Small(_, blocks) => by_index(blocks),
+        }
+    }
+
+    fn section_blocks(&self, section: usize, palette: Arc<Vec<S>>) -> impl Iterator<Item=(LocalBlockPosition, S)> {
+        let indices: Section<u16> = match &self.nodes {
+            Nodes::Large(nodes, _) => nodes[section].to_section(),
+            Nodes::Small(nodes, _) => nodes[section].to_section().map(u16::from),
+        };
+        let y = (section as u32) << 20;
+        (0..16 * 16 * 16).map(move |i| {
+            let pos = LocalBlockPosition { c: section_position(i).c + y };
+            (pos, palette[indices[i] as usize].clone())
+        })
+    }
+
+    /// `pos.y` counts from the bottom of the chunk rather than of a section.
+    ///
+    /// # Panics
+    /// If `pos.y` is at or above the 384 blocks of the chunk.
+    pub fn get(&self, pos: LocalBlockPosition) -> S {
+        assert!(pos.c >> 16 < 24 * 16, "y {} is above the top of the chunk", pos.c >> 16);
+        let (section, local) = ((pos.c >> 20) as usize, LocalBlockPosition { c: pos.c & 0x000f_ffff });
+        match &self.nodes {
+            Nodes::Large(nodes, blocks) => blocks.get_by_left(&nodes[section].get(local)).unwrap().clone(),
+            Nodes::Small(nodes, blocks) => blocks.get_by_left(&nodes[section].get(local)).unwrap().clone(),
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 struct BlockPosition {
-    x: u32,
+    x: i32,
     y: u16,
-    z: u32,
+    z: i32,
 }
 
-#[derive(Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct ChunkPosition {
+    x: i32,
+    z: i32,
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
 struct LocalBlockPosition {
     c: u32,
 }
@@ -661,14 +670,26 @@ impl LocalBlockPosition {
 }
 
 impl BlockPosition {
-    pub fn new(x: u32, y: u32, z: u32) -> Self {
+    pub fn new(x: i32, y: u32, z: i32) -> Self {
         Self { x, y: y as u16, z }
     }
 }
 
+impl ChunkPosition {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+}
+
+impl From<BlockPosition> for ChunkPosition {
+    fn from(p: BlockPosition) -> Self {
+        Self { x: p.x >> 4, z: p.z >> 4 }
+    }
+}
+
 impl From<BlockPosition> for LocalBlockPosition {
     fn from(p: BlockPosition) -> Self {
-        Self { c: (p.y as u32) << 16 | p.x << 8 | p.z }
+        Self { c: (p.y as u32) << 16 | ((p.x & 0x0f) as u32) << 8 | (p.z & 0x0f) as u32 }
     }
 }
 
@@ -723,16 +744,7 @@ fn main() {
     // }
     // println!("{:?}", s / 1024.0 / 1024.0);
     //
-    let mut a = BufReader::new(std::fs::File::open("./data/7_12.dat").unwrap());
-
-    let mut b = vec![];
-    while let Ok(v) = a.read_u16::<BigEndian>() {
-        b.push(v);
-    }
-    let mut a: Vec<Block> = unsafe { std::mem::transmute(b) };
-    a.shrink_to_fit();
-
-    let sections = unsafe { std::mem::transmute::<_, Box<[RawSection; 24]>>(a.leak().as_ptr()) };
+    let sections = dat::read_sections("./data/7_12.dat").unwrap();
 
     println!("{:?}", sections);
 
@@ -740,7 +752,7 @@ fn main() {
     //     println!("{:?}", (0..16).map(|z| sections[0].get(x + z * 16 + 1 * 256).unwrap()).collect::<Vec<_>>());
     // }
     //
-    let r = (0..128).into_iter().map(|_| {
+    let r = (0..128).map(|_| {
         let s = Instant::now();
         std::hint::black_box(ChunkData::from(sections.as_ref()));
         s.elapsed()
@@ -761,12 +773,155 @@ fn main() {
     //     println!("---\n");
     // }
 
-    // let mut file = std::fs::OpenOptions::new().create(true).write(true).truncate(true).open("./hi.dat").unwrap();
-    // let blocks = chunk.nodes.par_iter().flat_map(|n|
-    //     (0..16).flat_map(|y| (0..16).flat_map(move |z| (0..16).map(move |x| n.get(LocalBlockPosition::new(x, y, z)))))
-    //         .flat_map(|f| (f as u16).to_be_bytes()).collect::<Vec<_>>()
-    // ).collect::<Vec<_>>();
-    // file.write_all(blocks.as_slice()).unwrap();
+    let chunk = ChunkData::from(sections.as_ref());
+    let encoded = chunk.encode();
+    println!("{:?}", (encoded.len(), ChunkData::decode(&encoded).unwrap().to_raw_sections() == sections));
+    let json = serde_json::to_vec(&chunk).unwrap();
+    println!("{:?}", (json.len(), serde_json::from_slice::<ChunkData>(&json).unwrap().to_raw_sections() == sections));
+
+    chunk.write_dat("./hi.dat", DatFormat::U16).unwrap();
+
+    let s = Instant::now();
+    let world = World::load("./data").unwrap();
+    println!("{:?}", (world.len(), s.elapsed()));
+    println!("{:?}", world.get(BlockPosition::new(7 * 16 + 3, 70, 12 * 16 + 5)) == Some(chunk.get(LocalBlockPosition::new(3, 70, 5))));
 
     println!("{:?}", std::fs::read("./hi.dat").unwrap() == std::fs::read("./data/7_12.dat").unwrap());
+
+    for codec in codec::BUILTIN {
+        let framed = codec::encode_framed(codec, &chunk);
+        println!("{:?}", (codec.name(), codec::decode_framed(&framed).unwrap().to_raw_sections() == sections));
+    }
+    for (name, size) in codec::report("./data").unwrap() {
+        println!("{:>24} {:>10}", name, size);
+    }
+
+    let (mut octree, mut packed, mut stored, mut packed_count) = (0, 0, 0, 0);
+    for (_, chunk) in world.chunks() {
+        for section in chunk.to_raw_sections().iter() {
+            octree += packed::OctreeSection::from_raw(section).get_size();
+            packed += packed::PackedSection::from_raw(section).get_size();
+            let smallest = packed::StoredSection::smallest(section);
+            stored += smallest.get_size();
+            packed_count += smallest.is_packed() as usize;
+        }
+    }
+    println!("{:?}", (octree, packed, stored, packed_count, world.len() * 24));
+
+    let mut dag = dag::WorldDag::from_world(&world);
+    dag.shrink_to_fit();
+    let stats = dag.stats();
+    println!("{:?}", (stats.tree_nodes, stats.unique_nodes, stats.tree_bytes, stats.dag_bytes, stats.index_bytes));
+
+    for stats in leaf_size::report("./data").unwrap() {
+        println!("leaf {:>2} build {:>10.2?} lookup {:>10.2?} {:>10} bytes", stats.leaf, stats.build, stats.lookup, stats.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    /// An empty directory for a test to write into, unique to the process and `name`.
+    pub(crate) fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("testing-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn section() -> Section<u8> {
+        std::array::from_fn(|i| if i % 7 == 0 || i >= 3000 { 1 } else { 0 })
+    }
+
+    #[test]
+    fn node_round_trip() {
+        let node: Node<u8> = Node::new(section());
+        assert_eq!(node.to_section(), section());
+        assert!(node.iter().all(|(pos, value)| node.get(pos) == value));
+        assert_eq!(node.iter().map(|(_, value)| value).collect::<Vec<_>>(), section());
+    }
+
+    #[test]
+    fn leaves_cover_the_section_once() {
+        let node: Node<u8> = Node::new(section());
+        let mut covered = [0; 16 * 16 * 16];
+        for (pos, size, value) in node.leaves() {
+            let (x, y, z) = ((pos.c >> 8) & 0x0f, pos.c >> 16, pos.c & 0x0f);
+            for (dx, dy, dz) in (0..size as u32).flat_map(|dx| (0..size as u32).flat_map(move |dy| (0..size as u32).map(move |dz| (dx, dy, dz)))) {
+                let i = (x + dx + ((z + dz) << 4) + ((y + dy) << 8)) as usize;
+                covered[i] += 1;
+                assert_eq!(section()[i], value);
+            }
+        }
+        assert!(covered.iter().all(|c| *c == 1));
+    }
+
+    #[test]
+    fn node_replace() {
+        let mut node: Node<u8> = Node::new(section());
+        node.replace(LocalBlockPosition::new(0, 0, 0), LocalBlockPosition::new(15, 15, 15), 1, 2);
+        assert_eq!(node.to_section(), section().map(|v| if v == 1 { 2 } else { v }));
+        node.replace(LocalBlockPosition::new(0, 0, 0), LocalBlockPosition::new(15, 15, 15), 2, 0);
+        assert!(matches!(node, Node::Block(0)));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(LocalBlockPosition::new(1, 2, 3), BlockState::new(Block::Wool, 14));
+        let json = serde_json::to_vec(&chunk).unwrap();
+        assert_eq!(serde_json::from_slice::<ChunkData>(&json).unwrap().to_raw_sections(), chunk.to_raw_sections());
+    }
+
+    #[test]
+    fn deserialize_checks_nodes() {
+        fn with_root(root: Node<u8>) -> serde_json::Result<ChunkData> {
+            let mut nodes: Box<[Node<u8>; 24]> = Box::new(std::array::from_fn(|_| Node::Block(0)));
+            nodes[5] = root;
+            let chunk = ChunkData { nodes: Nodes::Small(nodes, BiBTreeMap::from_iter([(0, BlockState::AIR)])) };
+            serde_json::from_slice(&serde_json::to_vec(&chunk).unwrap())
+        }
+        fn deep(depth: usize) -> Node<u8> {
+            if depth == 0 { Node::Block(0) } else { Node::Nodes(Box::new(std::array::from_fn(|_| deep(depth - 1)))) }
+        }
+
+        assert!(with_root(deep(2)).is_ok());
+        assert!(with_root(deep(3)).unwrap_err().to_string().contains("not allowed in a node 4 blocks wide"));
+        assert!(with_root(Node::Blocks(Box::default())).unwrap_err().to_string().contains("not allowed in a node 16 blocks wide"));
+        assert!(with_root(Node::Block(1)).unwrap_err().to_string().contains("palette index 1 is not defined"));
+    }
+
+    #[test]
+    fn chunk_edits() {
+        let stone = BlockState::new(Block::Stone, 0);
+        let dirt = BlockState::new(Block::Dirt, 0);
+        let mut chunk = ChunkData::empty();
+        chunk.fill(LocalBlockPosition::new(0, 10, 0), LocalBlockPosition::new(15, 20, 15), stone);
+        chunk.replace(LocalBlockPosition::new(0, 0, 0), LocalBlockPosition::new(7, 383, 15), &stone, dirt);
+        chunk.set_block(LocalBlockPosition::new(3, 300, 3), stone);
+
+        assert_eq!(chunk.get(LocalBlockPosition::new(8, 15, 0)), stone);
+        assert_eq!(chunk.get(LocalBlockPosition::new(7, 15, 0)), dirt);
+        assert_eq!(chunk.get(LocalBlockPosition::new(7, 21, 0)), BlockState::AIR);
+
+        let blocks = chunk.iter().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 24 * 16 * 16 * 16);
+        assert!(blocks.iter().all(|(pos, block)| chunk.get(*pos) == *block));
+        assert_eq!(chunk.par_iter().collect::<Vec<_>>(), blocks);
+        assert_eq!(blocks.iter().filter(|(_, block)| *block == stone).count(), 8 * 11 * 16 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "y 384 is above the top of the chunk")]
+    fn set_block_above_the_chunk() {
+        ChunkData::empty().set_block(LocalBlockPosition::new(0, 384, 0), BlockState::AIR);
+    }
+
+    #[test]
+    #[should_panic(expected = "y 400 is above the top of the chunk")]
+    fn get_above_the_chunk() {
+        ChunkData::empty().get(LocalBlockPosition::new(0, 400, 0));
+    }
 }
@@ -0,0 +1,345 @@
+//! Named Binary Tag, the big-endian tree format Minecraft stores chunks and schematics in.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// Nesting deeper than this is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// Stored unsigned, block arrays are easier to index that way.
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(BTreeMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+#[derive(Debug)]
+pub enum NbtError {
+    Io(std::io::Error),
+    UnknownTag(u8),
+    /// The root of a document must be a compound.
+    RootNotCompound(u8),
+    NegativeLength(i32),
+    /// A non-empty list of `TAG_End`.
+    BadList,
+    TooDeep,
+    InvalidString,
+}
+
+impl Display for NbtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::UnknownTag(tag) => write!(f, "unknown tag type {}", tag),
+            Self::RootNotCompound(tag) => write!(f, "root tag has type {}, expected a compound", tag),
+            Self::NegativeLength(len) => write!(f, "negative length {}", len),
+            Self::BadList => f.write_str("non-empty list of TAG_End"),
+            Self::TooDeep => write!(f, "tags nested deeper than {}", MAX_DEPTH),
+            Self::InvalidString => f.write_str("string is not valid modified UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for NbtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for NbtError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl Tag {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.as_compound()?.get(name)
+    }
+
+    pub fn as_compound(&self) -> Option<&BTreeMap<String, Tag>> {
+        match self {
+            Self::Compound(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Self::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte_array(&self) -> Option<&[u8]> {
+        match self {
+            Self::ByteArray(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any integer tag, widened.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Byte(v) => Some(v as i64),
+            Self::Short(v) => Some(v as i64),
+            Self::Int(v) => Some(v as i64),
+            Self::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Self::Byte(_) => TAG_BYTE,
+            Self::Short(_) => TAG_SHORT,
+            Self::Int(_) => TAG_INT,
+            Self::Long(_) => TAG_LONG,
+            Self::Float(_) => TAG_FLOAT,
+            Self::Double(_) => TAG_DOUBLE,
+            Self::ByteArray(_) => TAG_BYTE_ARRAY,
+            Self::String(_) => TAG_STRING,
+            Self::List(_) => TAG_LIST,
+            Self::Compound(_) => TAG_COMPOUND,
+            Self::IntArray(_) => TAG_INT_ARRAY,
+            Self::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+}
+
+/// Builds a compound from `(name, tag)` pairs.
+pub fn compound<const N: usize>(entries: [(&str, Tag); N]) -> Tag {
+    Tag::Compound(entries.into_iter().map(|(name, tag)| (name.to_string(), tag)).collect())
+}
+
+fn read_len(r: &mut impl Read) -> Result<usize, NbtError> {
+    let len = r.read_i32::<BigEndian>()?;
+    usize::try_from(len).map_err(|_| NbtError::NegativeLength(len))
+}
+
+fn read_string(r: &mut impl Read) -> Result<String, NbtError> {
+    let mut bytes = vec![0; r.read_u16::<BigEndian>()? as usize];
+    r.read_exact(&mut bytes)?;
+    // Java writes modified UTF-8, which only differs from UTF-8 for NUL and astral characters.
+    String::from_utf8(bytes).map_err(|_| NbtError::InvalidString)
+}
+
+fn read_payload(r: &mut impl Read, id: u8, depth: usize) -> Result<Tag, NbtError> {
+    if depth > MAX_DEPTH {
+        return Err(NbtError::TooDeep);
+    }
+
+    Ok(match id {
+        TAG_BYTE => Tag::Byte(r.read_i8()?),
+        TAG_SHORT => Tag::Short(r.read_i16::<BigEndian>()?),
+        TAG_INT => Tag::Int(r.read_i32::<BigEndian>()?),
+        TAG_LONG => Tag::Long(r.read_i64::<BigEndian>()?),
+        TAG_FLOAT => Tag::Float(r.read_f32::<BigEndian>()?),
+        TAG_DOUBLE => Tag::Double(r.read_f64::<BigEndian>()?),
+        TAG_BYTE_ARRAY => {
+            let len = read_len(r)?;
+            let mut bytes = vec![];
+            r.take(len as u64).read_to_end(&mut bytes)?;
+            if bytes.len() != len {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            Tag::ByteArray(bytes)
+        }
+        TAG_STRING => Tag::String(read_string(r)?),
+        TAG_LIST => {
+            let element = r.read_u8()?;
+            let len = read_len(r)?;
+            if element == TAG_END && len != 0 {
+                return Err(NbtError::BadList);
+            }
+            Tag::List((0..len).map(|_| read_payload(r, element, depth + 1)).collect::<Result<_, _>>()?)
+        }
+        TAG_COMPOUND => {
+            let mut compound = BTreeMap::new();
+            loop {
+                let id = r.read_u8()?;
+                if id == TAG_END {
+                    break;
+                }
+                let name = read_string(r)?;
+                compound.insert(name, read_payload(r, id, depth + 1)?);
+            }
+            Tag::Compound(compound)
+        }
+        TAG_INT_ARRAY => Tag::IntArray((0..read_len(r)?).map(|_| r.read_i32::<BigEndian>()).collect::<Result<_, _>>()?),
+        TAG_LONG_ARRAY => Tag::LongArray((0..read_len(r)?).map(|_| r.read_i64::<BigEndian>()).collect::<Result<_, _>>()?),
+        id => return Err(NbtError::UnknownTag(id)),
+    })
+}
+
+/// Reads an uncompressed document, returning the root's name and compound.
+pub fn read(r: &mut impl Read) -> Result<(String, Tag), NbtError> {
+    match r.read_u8()? {
+        TAG_COMPOUND => {}
+        id => return Err(NbtError::RootNotCompound(id)),
+    }
+    let name = read_string(r)?;
+    Ok((name, read_payload(r, TAG_COMPOUND, 0)?))
+}
+
+/// Reads a document that may be gzip or zlib compressed, as both occur in the wild.
+pub fn read_compressed(bytes: &[u8]) -> Result<(String, Tag), NbtError> {
+    match bytes {
+        [0x1f, 0x8b, ..] => read(&mut GzDecoder::new(bytes)),
+        [0x78, ..] => read(&mut ZlibDecoder::new(bytes)),
+        _ => read(&mut &bytes[..]),
+    }
+}
+
+fn write_string(w: &mut impl Write, s: &str) -> std::io::Result<()> {
+    w.write_u16::<BigEndian>(s.len() as u16)?;
+    w.write_all(s.as_bytes())
+}
+
+fn write_payload(w: &mut impl Write, tag: &Tag) -> std::io::Result<()> {
+    match tag {
+        Tag::Byte(v) => w.write_i8(*v),
+        Tag::Short(v) => w.write_i16::<BigEndian>(*v),
+        Tag::Int(v) => w.write_i32::<BigEndian>(*v),
+        Tag::Long(v) => w.write_i64::<BigEndian>(*v),
+        Tag::Float(v) => w.write_f32::<BigEndian>(*v),
+        Tag::Double(v) => w.write_f64::<BigEndian>(*v),
+        Tag::ByteArray(bytes) => {
+            w.write_i32::<BigEndian>(bytes.len() as i32)?;
+            w.write_all(bytes)
+        }
+        Tag::String(s) => write_string(w, s),
+        Tag::List(list) => {
+            // Lists are homogeneous, the first element decides; empty lists are written as `TAG_End`.
+            w.write_u8(list.first().map_or(TAG_END, Tag::id))?;
+            w.write_i32::<BigEndian>(list.len() as i32)?;
+            list.iter().try_for_each(|tag| write_payload(w, tag))
+        }
+        Tag::Compound(compound) => {
+            for (name, tag) in compound {
+                w.write_u8(tag.id())?;
+                write_string(w, name)?;
+                write_payload(w, tag)?;
+            }
+            w.write_u8(TAG_END)
+        }
+        Tag::IntArray(values) => {
+            w.write_i32::<BigEndian>(values.len() as i32)?;
+            values.iter().try_for_each(|v| w.write_i32::<BigEndian>(*v))
+        }
+        Tag::LongArray(values) => {
+            w.write_i32::<BigEndian>(values.len() as i32)?;
+            values.iter().try_for_each(|v| w.write_i64::<BigEndian>(*v))
+        }
+    }
+}
+
+/// Writes an uncompressed document. `root` should be a compound.
+pub fn write(w: &mut impl Write, name: &str, root: &Tag) -> std::io::Result<()> {
+    w.write_u8(root.id())?;
+    write_string(w, name)?;
+    write_payload(w, root)
+}
+
+pub fn write_gzip(name: &str, root: &Tag) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    write(&mut encoder, name, root).unwrap();
+    encoder.finish().unwrap()
+}
+
+pub fn write_zlib(name: &str, root: &Tag) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    write(&mut encoder, name, root).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_tag() -> Tag {
+        compound([
+            ("byte", Tag::Byte(-1)),
+            ("short", Tag::Short(-300)),
+            ("int", Tag::Int(1 << 20)),
+            ("long", Tag::Long(-1 << 40)),
+            ("float", Tag::Float(0.5)),
+            ("double", Tag::Double(-2.25)),
+            ("bytes", Tag::ByteArray(vec![0, 127, 255])),
+            ("string", Tag::String("minecraft:stone ☃".to_string())),
+            ("list", Tag::List(vec![Tag::Short(1), Tag::Short(2)])),
+            ("empty list", Tag::List(vec![])),
+            ("compound", compound([("nested", compound([]))])),
+            ("ints", Tag::IntArray(vec![i32::MIN, 0, i32::MAX])),
+            ("longs", Tag::LongArray(vec![i64::MIN, i64::MAX])),
+        ])
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = vec![];
+        write(&mut bytes, "root", &every_tag()).unwrap();
+        assert_eq!(read(&mut bytes.as_slice()).unwrap(), ("root".to_string(), every_tag()));
+        assert_eq!(read_compressed(&bytes).unwrap().1, every_tag());
+        assert_eq!(read_compressed(&write_gzip("", &every_tag())).unwrap().1, every_tag());
+        assert_eq!(read_compressed(&write_zlib("", &every_tag())).unwrap().1, every_tag());
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert!(matches!(read(&mut [TAG_INT, 0, 0].as_slice()), Err(NbtError::RootNotCompound(TAG_INT))));
+        assert!(matches!(read(&mut [TAG_COMPOUND, 0, 0, 99, 0, 0].as_slice()), Err(NbtError::UnknownTag(99))));
+
+        let mut bytes = vec![];
+        write(&mut bytes, "", &compound([("bytes", Tag::ByteArray(vec![]))])).unwrap();
+        let len = bytes.len() - 5;
+        bytes[len..len + 4].copy_from_slice(&(-1i32).to_be_bytes());
+        assert!(matches!(read(&mut bytes.as_slice()), Err(NbtError::NegativeLength(-1))));
+
+        let mut deep = vec![TAG_COMPOUND, 0, 0];
+        for _ in 0..MAX_DEPTH + 1 {
+            deep.extend_from_slice(&[TAG_COMPOUND, 0, 0]);
+        }
+        assert!(matches!(read(&mut deep.as_slice()), Err(NbtError::TooDeep)));
+    }
+}
@@ -0,0 +1,355 @@
+//! Many encoded chunks in one file.
+//!
+//! All integers are big-endian.
+//!
+//! ```text
+//! header  magic "OCTP", version: u8, codec id: u8, 2 reserved bytes, index offset: u64, index count: u32
+//! data    chunks encoded with the codec, back to back
+//! index   count times { x: i32, z: i32, offset: u64, length: u32 }
+//! ```
+//!
+//! Writes only ever append: new chunk data and then a fresh index go to the end of the file, and
+//! the header is repointed last, so a torn write leaves the previous index intact. Replaced chunks
+//! and old indices become dead space until [`Pack::compact`].
+//!
+//! The codec id is one of [`crate::codec::builtin`]; packs from before codecs have 0 there, the octree.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{ChunkData, ChunkPosition};
+use crate::codec::{builtin, ChunkCodec, CodecError, OCTREE};
+use crate::world::World;
+
+pub const MAGIC: [u8; 4] = *b"OCTP";
+pub const VERSION: u8 = 1;
+const HEADER_SIZE: u64 = 20;
+const ENTRY_SIZE: u64 = 20;
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(std::io::Error),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u8),
+    UnknownCodec(u8),
+    /// The index or one of its entries points outside the file.
+    CorruptIndex,
+    Decode { pos: ChunkPosition, error: CodecError },
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::BadMagic(magic) => write!(f, "bad magic {:?}", magic),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            Self::UnknownCodec(id) => write!(f, "unknown codec {}", id),
+            Self::CorruptIndex => f.write_str("index points outside the file"),
+            Self::Decode { pos, error } => write!(f, "chunk {:?}: {}", pos, error),
+        }
+    }
+}
+
+impl std::error::Error for PackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PackError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Entry {
+    offset: u64,
+    len: u32,
+}
+
+pub struct Pack {
+    path: PathBuf,
+    file: File,
+    codec: &'static dyn ChunkCodec,
+    index: BTreeMap<ChunkPosition, Entry>,
+}
+
+impl Pack {
+    /// Creates an empty pack of octree encoded chunks, truncating any existing file.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::create_with_codec(path, &OCTREE)
+    }
+
+    /// Like [`Pack::create`], with every chunk written by `codec`.
+    pub fn create_with_codec(path: impl AsRef<Path>, codec: &'static dyn ChunkCodec) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        let mut pack = Self { path, file, codec, index: BTreeMap::new() };
+        pack.commit(HEADER_SIZE)?;
+        Ok(pack)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PackError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let file_len = file.metadata()?.len();
+
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(PackError::BadMagic(magic));
+        }
+        match file.read_u8()? {
+            VERSION => {}
+            v => return Err(PackError::UnsupportedVersion(v)),
+        }
+        let id = file.read_u8()?;
+        let codec = builtin(id).ok_or(PackError::UnknownCodec(id))?;
+        file.read_exact(&mut [0; 2])?;
+        let index_offset = file.read_u64::<BigEndian>()?;
+        let count = file.read_u32::<BigEndian>()? as u64;
+        let index_end = index_offset.checked_add(count * ENTRY_SIZE).ok_or(PackError::CorruptIndex)?;
+        if index_offset < HEADER_SIZE || index_end > file_len {
+            return Err(PackError::CorruptIndex);
+        }
+
+        file.seek(SeekFrom::Start(index_offset))?;
+        let mut raw = vec![0; (count * ENTRY_SIZE) as usize];
+        file.read_exact(&mut raw)?;
+        let mut r = raw.as_slice();
+        let mut index = BTreeMap::new();
+        for _ in 0..count {
+            let pos = ChunkPosition::new(r.read_i32::<BigEndian>()?, r.read_i32::<BigEndian>()?);
+            let entry = Entry { offset: r.read_u64::<BigEndian>()?, len: r.read_u32::<BigEndian>()? };
+            let end = entry.offset.checked_add(entry.len as u64).ok_or(PackError::CorruptIndex)?;
+            if entry.offset < HEADER_SIZE || end > index_offset {
+                return Err(PackError::CorruptIndex);
+            }
+            index.insert(pos, entry);
+        }
+
+        Ok(Self { path, file, codec, index })
+    }
+
+    pub fn codec(&self) -> &'static dyn ChunkCodec {
+        self.codec
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn contains(&self, pos: ChunkPosition) -> bool {
+        self.index.contains_key(&pos)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item=&ChunkPosition> {
+        self.index.keys()
+    }
+
+    /// The encoded bytes of one chunk, read with a single seek.
+    pub fn read_raw(&mut self, pos: ChunkPosition) -> std::io::Result<Option<Vec<u8>>> {
+        let Some(entry) = self.index.get(&pos).copied() else { return Ok(None) };
+        let mut bytes = vec![0; entry.len as usize];
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    pub fn read(&mut self, pos: ChunkPosition) -> Result<Option<ChunkData>, PackError> {
+        match self.read_raw(pos)? {
+            Some(bytes) => self.codec.decode(&bytes).map(Some).map_err(|error| PackError::Decode { pos, error }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert(&mut self, pos: ChunkPosition, chunk: &ChunkData) -> std::io::Result<()> {
+        self.extend([(pos, chunk)])
+    }
+
+    /// Appends the chunks and commits a single new index. Positions already present are replaced.
+    pub fn extend<'a>(&mut self, chunks: impl IntoIterator<Item=(ChunkPosition, &'a ChunkData)>) -> std::io::Result<()> {
+        let end = self.file.seek(SeekFrom::End(0))?;
+        let mut w = BufWriter::new(&self.file);
+        let mut offset = end;
+        for (pos, chunk) in chunks {
+            let bytes = self.codec.encode(chunk);
+            w.write_all(&bytes)?;
+            self.index.insert(pos, Entry { offset, len: bytes.len() as u32 });
+            offset += bytes.len() as u64;
+        }
+        w.flush()?;
+        drop(w);
+        self.commit(offset)
+    }
+
+    pub fn remove(&mut self, pos: ChunkPosition) -> std::io::Result<bool> {
+        if self.index.remove(&pos).is_none() {
+            return Ok(false);
+        }
+        let end = self.file.seek(SeekFrom::End(0))?;
+        self.commit(end)?;
+        Ok(true)
+    }
+
+    /// Bytes no longer reachable from the index.
+    pub fn dead_space(&self) -> std::io::Result<u64> {
+        let live = HEADER_SIZE + self.index.len() as u64 * ENTRY_SIZE + self.index.values().map(|e| e.len as u64).sum::<u64>();
+        Ok(self.file.metadata()?.len() - live)
+    }
+
+    /// Rewrites the file with only the live chunks, via a temporary file renamed over the original.
+    pub fn compact(&mut self) -> std::io::Result<()> {
+        let tmp = self.path.with_extension("compacting");
+        let mut compacted = Self::create_with_codec(&tmp, self.codec)?;
+        let mut w = BufWriter::new(&compacted.file);
+        w.seek(SeekFrom::Start(HEADER_SIZE))?;
+        let mut offset = HEADER_SIZE;
+        for (pos, entry) in &self.index {
+            let mut bytes = vec![0; entry.len as usize];
+            self.file.seek(SeekFrom::Start(entry.offset))?;
+            self.file.read_exact(&mut bytes)?;
+            w.write_all(&bytes)?;
+            compacted.index.insert(*pos, Entry { offset, len: entry.len });
+            offset += entry.len as u64;
+        }
+        w.flush()?;
+        drop(w);
+        compacted.file.set_len(offset)?;
+        compacted.commit(offset)?;
+
+        std::fs::rename(&tmp, &self.path)?;
+        compacted.path = self.path.clone();
+        *self = compacted;
+        Ok(())
+    }
+
+    /// Writes the index at `at` and then points the header at it.
+    fn commit(&mut self, at: u64) -> std::io::Result<()> {
+        let mut index = Vec::with_capacity(self.index.len() * ENTRY_SIZE as usize);
+        for (pos, entry) in &self.index {
+            index.write_i32::<BigEndian>(pos.x)?;
+            index.write_i32::<BigEndian>(pos.z)?;
+            index.write_u64::<BigEndian>(entry.offset)?;
+            index.write_u32::<BigEndian>(entry.len)?;
+        }
+        self.file.seek(SeekFrom::Start(at))?;
+        self.file.write_all(&index)?;
+        self.file.sync_data()?;
+
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&[VERSION, self.codec.id(), 0, 0]);
+        header.write_u64::<BigEndian>(at)?;
+        header.write_u32::<BigEndian>(self.index.len() as u32)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.sync_data()
+    }
+}
+
+impl World {
+    pub fn write_pack(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        Pack::create(path)?.extend(self.chunks().map(|(pos, chunk)| (*pos, chunk)))
+    }
+
+    pub fn read_pack(path: impl AsRef<Path>) -> Result<Self, PackError> {
+        let mut pack = Pack::open(path)?;
+        let mut world = World::new();
+        for pos in pack.positions().copied().collect::<Vec<_>>() {
+            world.insert(pos, pack.read(pos)?.unwrap());
+        }
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let path = crate::tests::temp_dir("pack-round-trip").join("world.octp");
+        let mut pack = Pack::create(&path).unwrap();
+        pack.insert(ChunkPosition::new(1, -2), &ChunkData::empty()).unwrap();
+        drop(pack);
+
+        let mut pack = Pack::open(&path).unwrap();
+        assert_eq!(pack.len(), 1);
+        assert!(pack.read(ChunkPosition::new(1, -2)).unwrap().is_some());
+        assert!(pack.read(ChunkPosition::new(0, 0)).unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replace_remove_and_compact() {
+        let path = crate::tests::temp_dir("pack-compact").join("world.octp");
+        let mut world = World::new();
+        for x in 0..3 {
+            world.insert(ChunkPosition::new(x, 0), ChunkData::empty());
+        }
+        world.write_pack(&path).unwrap();
+
+        let mut pack = Pack::open(&path).unwrap();
+        assert_eq!(pack.codec().id(), OCTREE.id());
+        assert_eq!(pack.dead_space().unwrap(), 0);
+        pack.insert(ChunkPosition::new(0, 0), &ChunkData::empty()).unwrap();
+        assert!(pack.remove(ChunkPosition::new(1, 0)).unwrap());
+        assert!(!pack.remove(ChunkPosition::new(1, 0)).unwrap());
+        assert!(!pack.contains(ChunkPosition::new(1, 0)));
+        assert!(pack.dead_space().unwrap() > 0);
+
+        pack.compact().unwrap();
+        assert_eq!(pack.dead_space().unwrap(), 0);
+        assert!(!pack.is_empty());
+        let world = World::read_pack(&path).unwrap();
+        assert_eq!(world.len(), 2);
+        assert!(world.chunk(ChunkPosition::new(2, 0)).is_some());
+    }
+
+    #[test]
+    fn keeps_its_codec() {
+        let path = crate::tests::temp_dir("pack-codec").join("world.octp");
+        let mut pack = Pack::create_with_codec(&path, &crate::codec::HYBRID_RLE).unwrap();
+        pack.insert(ChunkPosition::new(0, 0), &ChunkData::empty()).unwrap();
+        drop(pack);
+        assert_eq!(Pack::open(&path).unwrap().codec().id(), crate::codec::HYBRID_RLE.id());
+    }
+
+    #[test]
+    fn rejects_overflowing_index() {
+        let path = crate::tests::temp_dir("pack-overflowing-index").join("world.octp");
+        let mut pack = Pack::create(&path).unwrap();
+        pack.insert(ChunkPosition::new(0, 0), &ChunkData::empty()).unwrap();
+        drop(pack);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8..16].copy_from_slice(&u64::MAX.to_be_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(Pack::open(&path), Err(PackError::CorruptIndex)));
+
+        // a valid index whose entry runs past the end of the address space
+        let mut pack = Pack::create(&path).unwrap();
+        pack.insert(ChunkPosition::new(0, 0), &ChunkData::empty()).unwrap();
+        drop(pack);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let entry = bytes.len() - ENTRY_SIZE as usize;
+        bytes[entry + 8..entry + 16].copy_from_slice(&(u64::MAX - 1).to_be_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(Pack::open(&path), Err(PackError::CorruptIndex)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
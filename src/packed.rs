@@ -0,0 +1,366 @@
+//! The vanilla alternative to the octree: a palette per section and its indices bit-packed into
+//! longs, so the two can be compared and mixed per section.
+
+use get_size::GetSize;
+
+use crate::{BlockState, ChunkData, LocalBlockPosition, Node, Nodes, PaletteIndex, RawSection};
+
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+
+fn section_index(pos: LocalBlockPosition) -> usize {
+    let (x, y, z) = ((pos.c >> 8) & 0x0f, (pos.c >> 16) & 0x0f, pos.c & 0x0f);
+    (x + (z << 4) + (y << 8)) as usize
+}
+
+/// One 16³ section of block states. Positions are section-relative.
+pub trait SectionStorage: GetSize {
+    fn from_raw(raw: &RawSection) -> Self where Self: Sized;
+
+    #[allow(dead_code)]
+    fn to_raw(&self) -> RawSection;
+
+    #[allow(dead_code)]
+    fn get(&self, pos: LocalBlockPosition) -> BlockState;
+
+    #[allow(dead_code)]
+    fn set(&mut self, pos: LocalBlockPosition, block: BlockState);
+}
+
+/// 1.16+ layout: as many `bits`-wide entries as fit in each long, lowest bits first, none
+/// spanning two longs. Unlike vanilla, a single-block section has zero bits and no data.
+#[derive(Clone, Debug, PartialEq, Eq, GetSize)]
+pub struct PackedSection {
+    palette: Vec<BlockState>,
+    bits: u32,
+    data: Vec<u64>,
+}
+
+impl PackedSection {
+    /// Bits per entry vanilla uses for a palette of `len` entries, 4 at least.
+    fn bits_for(len: usize) -> u32 {
+        match len {
+            0 | 1 => 0,
+            len => (usize::BITS - (len - 1).leading_zeros()).max(4),
+        }
+    }
+
+    fn pack(bits: u32, indices: impl Iterator<Item=usize>) -> Vec<u64> {
+        if bits == 0 {
+            return vec![];
+        }
+        let per_long = (64 / bits) as usize;
+        let mut data = vec![0; SECTION_VOLUME.div_ceil(per_long)];
+        for (i, index) in indices.enumerate() {
+            data[i / per_long] |= (index as u64) << ((i % per_long) as u32 * bits);
+        }
+        data
+    }
+
+    #[allow(dead_code)]
+    pub fn palette(&self) -> &[BlockState] {
+        &self.palette
+    }
+
+    #[allow(dead_code)]
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    #[allow(dead_code)]
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    fn index(&self, i: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_long = (64 / self.bits) as usize;
+        ((self.data[i / per_long] >> ((i % per_long) as u32 * self.bits)) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn indices(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..SECTION_VOLUME).map(|i| self.index(i))
+    }
+}
+
+impl SectionStorage for PackedSection {
+    fn from_raw(raw: &RawSection) -> Self {
+        let mut palette = vec![];
+        let indices: Vec<usize> = raw.iter()
+            .map(|block| match palette.iter().position(|b| b == block) {
+                Some(i) => i,
+                None => {
+                    palette.push(*block);
+                    palette.len() - 1
+                }
+            })
+            .collect();
+        let bits = Self::bits_for(palette.len());
+        Self { data: Self::pack(bits, indices.into_iter()), palette, bits }
+    }
+
+    fn to_raw(&self) -> RawSection {
+        let mut raw = [BlockState::AIR; SECTION_VOLUME];
+        raw.iter_mut().zip(self.indices()).for_each(|(b, i)| *b = self.palette[i]);
+        raw
+    }
+
+    fn get(&self, pos: LocalBlockPosition) -> BlockState {
+        self.palette[self.index(section_index(pos))]
+    }
+
+    /// New blocks are appended to the palette, repacking the data once it outgrows `bits`. Blocks
+    /// no longer used stay in the palette.
+    fn set(&mut self, pos: LocalBlockPosition, block: BlockState) {
+        let index = match self.palette.iter().position(|b| *b == block) {
+            Some(index) => index,
+            None => {
+                self.palette.push(block);
+                self.palette.len() - 1
+            }
+        };
+
+        let bits = Self::bits_for(self.palette.len());
+        if bits != self.bits {
+            let indices: Vec<usize> = self.indices().collect();
+            self.data = Self::pack(bits, indices.into_iter());
+            self.bits = bits;
+        }
+        if self.bits == 0 {
+            return;
+        }
+
+        let i = section_index(pos);
+        let per_long = (64 / self.bits) as usize;
+        let shift = (i % per_long) as u32 * self.bits;
+        let long = &mut self.data[i / per_long];
+        *long = (*long & !(((1 << self.bits) - 1) << shift)) | (index as u64) << shift;
+    }
+}
+
+/// An octree with a palette of its own, as opposed to the chunk-wide one of [`ChunkData`].
+#[derive(Clone, Debug)]
+pub struct OctreeSection {
+    palette: Vec<BlockState>,
+    node: Node<u16>,
+}
+
+impl OctreeSection {
+    #[allow(dead_code)]
+    pub fn palette(&self) -> &[BlockState] {
+        &self.palette
+    }
+
+    fn index_of(&mut self, block: BlockState) -> u16 {
+        match self.palette.iter().position(|b| *b == block) {
+            Some(index) => index as u16,
+            None => {
+                self.palette.push(block);
+                self.palette.len() as u16 - 1
+            }
+        }
+    }
+}
+
+impl GetSize for OctreeSection {
+    fn get_heap_size(&self) -> usize {
+        self.palette.get_heap_size() + self.node.get_heap_size()
+    }
+}
+
+impl SectionStorage for OctreeSection {
+    fn from_raw(raw: &RawSection) -> Self {
+        let mut section = Self { palette: vec![], node: Node::Block(0) };
+        let indices = raw.map(|block| section.index_of(block));
+        section.node = Node::new(indices);
+        section
+    }
+
+    fn to_raw(&self) -> RawSection {
+        self.node.to_section().map(|i| self.palette[i as usize])
+    }
+
+    fn get(&self, pos: LocalBlockPosition) -> BlockState {
+        self.palette[self.node.get(LocalBlockPosition { c: pos.c & 0x000f_ffff }) as usize]
+    }
+
+    fn set(&mut self, pos: LocalBlockPosition, block: BlockState) {
+        let index = self.index_of(block);
+        self.node.set(LocalBlockPosition { c: pos.c & 0x000f_ffff }, index);
+    }
+}
+
+impl From<&PackedSection> for OctreeSection {
+    fn from(packed: &PackedSection) -> Self {
+        let indices: Vec<u16> = packed.indices().map(|i| i as u16).collect();
+        Self { palette: packed.palette.clone(), node: Node::new(indices.try_into().unwrap()) }
+    }
+}
+
+impl From<&OctreeSection> for PackedSection {
+    fn from(octree: &OctreeSection) -> Self {
+        let bits = Self::bits_for(octree.palette.len());
+        let data = Self::pack(bits, octree.node.to_section().into_iter().map(usize::from));
+        Self { palette: octree.palette.clone(), bits, data }
+    }
+}
+
+/// Whichever of the two representations a section is smaller in.
+#[derive(Clone, Debug)]
+pub enum StoredSection {
+    Octree(OctreeSection),
+    Packed(PackedSection),
+}
+
+impl GetSize for StoredSection {
+    fn get_heap_size(&self) -> usize {
+        match self {
+            Self::Octree(section) => section.get_heap_size(),
+            Self::Packed(section) => section.get_heap_size(),
+        }
+    }
+}
+
+impl StoredSection {
+    /// Builds both and keeps the smaller by [`GetSize`], preferring the octree on a tie.
+    pub fn smallest(raw: &RawSection) -> Self {
+        let (octree, packed) = (OctreeSection::from_raw(raw), PackedSection::from_raw(raw));
+        if packed.get_size() < octree.get_size() { Self::Packed(packed) } else { Self::Octree(octree) }
+    }
+
+    pub fn is_packed(&self) -> bool {
+        matches!(self, Self::Packed(_))
+    }
+}
+
+impl SectionStorage for StoredSection {
+    fn from_raw(raw: &RawSection) -> Self {
+        Self::smallest(raw)
+    }
+
+    fn to_raw(&self) -> RawSection {
+        match self {
+            Self::Octree(section) => section.to_raw(),
+            Self::Packed(section) => section.to_raw(),
+        }
+    }
+
+    fn get(&self, pos: LocalBlockPosition) -> BlockState {
+        match self {
+            Self::Octree(section) => section.get(pos),
+            Self::Packed(section) => section.get(pos),
+        }
+    }
+
+    /// Stays in the current representation, call [`StoredSection::smallest`] again to re-pick.
+    fn set(&mut self, pos: LocalBlockPosition, block: BlockState) {
+        match self {
+            Self::Octree(section) => section.set(pos, block),
+            Self::Packed(section) => section.set(pos, block),
+        }
+    }
+}
+
+impl ChunkData {
+    /// Bottom section first, like the octree nodes.
+    #[allow(dead_code)]
+    pub fn to_packed_sections(&self) -> Vec<PackedSection> {
+        fn pack<T: PaletteIndex>(nodes: &[Node<T>; 24], blocks: &bimap::BiBTreeMap<T, BlockState>) -> Vec<PackedSection> {
+            nodes.iter()
+                .map(|node| PackedSection::from_raw(&node.to_section().map(|id| *blocks.get_by_left(&id).unwrap())))
+                .collect()
+        }
+
+        match &self.nodes {
+            Nodes::Large(nodes, blocks) => pack(nodes, blocks),
+            Nodes::Small(nodes, blocks) => pack(nodes, blocks),
+        }
+    }
+
+    /// The smaller representation of every section, bottom first.
+    #[allow(dead_code)]
+    pub fn to_stored_sections(&self) -> Vec<StoredSection> {
+        self.to_raw_sections().iter().map(StoredSection::smallest).collect()
+    }
+
+    /// `sections` holds 24 sections, bottom first.
+    #[allow(dead_code)]
+    pub fn from_sections<S: SectionStorage>(sections: &[S]) -> Self {
+        assert_eq!(sections.len(), 24, "a chunk has 24 sections");
+        let raw: Box<[RawSection]> = sections.iter().map(S::to_raw).collect();
+        let raw: Box<crate::RawSections> = raw.try_into().unwrap();
+        ChunkData::from(raw.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    fn raw() -> RawSection {
+        std::array::from_fn(|i| match i % 5 {
+            0 => BlockState::from(Block::Stone),
+            1 => BlockState::new(Block::Wool, (i % 16) as u8),
+            _ => BlockState::AIR,
+        })
+    }
+
+    fn round_trip<S: SectionStorage>() {
+        let mut section = S::from_raw(&raw());
+        assert_eq!(section.to_raw(), raw());
+
+        let pos = LocalBlockPosition::new(3, 7, 11);
+        assert_eq!(section.get(pos), raw()[section_index(pos)]);
+        section.set(pos, BlockState::from(Block::Glowstone));
+        let mut expected = raw();
+        expected[section_index(pos)] = BlockState::from(Block::Glowstone);
+        assert_eq!(section.to_raw(), expected);
+        assert_eq!(section.get(pos), BlockState::from(Block::Glowstone));
+    }
+
+    #[test]
+    fn storages_round_trip() {
+        round_trip::<PackedSection>();
+        round_trip::<OctreeSection>();
+        round_trip::<StoredSection>();
+    }
+
+    #[test]
+    fn packed_layout() {
+        let uniform = PackedSection::from_raw(&[BlockState::AIR; SECTION_VOLUME]);
+        assert_eq!((uniform.palette(), uniform.bits(), uniform.data()), (&[BlockState::AIR][..], 0, &[][..]));
+
+        let mut section = PackedSection::from_raw(&raw());
+        assert_eq!((section.palette().len(), section.bits()), (18, 5));
+        assert_eq!(section.data().len(), SECTION_VOLUME.div_ceil(12));
+        for data in 0..16 {
+            section.set(LocalBlockPosition::new(0, 0, 0), BlockState::new(Block::Wood, data));
+        }
+        assert_eq!(section.bits(), 6);
+        assert_eq!(section.get(LocalBlockPosition::new(0, 0, 0)), BlockState::new(Block::Wood, 15));
+    }
+
+    #[test]
+    fn conversions() {
+        let packed = PackedSection::from_raw(&raw());
+        let octree = OctreeSection::from(&packed);
+        assert_eq!(octree.palette(), packed.palette());
+        assert_eq!(octree.to_raw(), raw());
+        assert_eq!(PackedSection::from(&octree), packed);
+        assert!(!StoredSection::smallest(&[BlockState::AIR; SECTION_VOLUME]).is_packed());
+    }
+
+    #[test]
+    fn chunk_sections() {
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(LocalBlockPosition::new(1, 200, 1), BlockState::from(Block::Stone));
+
+        let packed = chunk.to_packed_sections();
+        assert_eq!(packed.len(), 24);
+        assert_eq!(packed[12].get(LocalBlockPosition::new(1, 8, 1)), BlockState::from(Block::Stone));
+        assert_eq!(ChunkData::from_sections(&packed).to_raw_sections(), chunk.to_raw_sections());
+        assert_eq!(ChunkData::from_sections(&chunk.to_stored_sections()).to_raw_sections(), chunk.to_raw_sections());
+    }
+}
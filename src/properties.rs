@@ -0,0 +1,408 @@
+//! Static per-block properties of 1.12, for lighting, meshing, physics and the like.
+
+use crate::block::{Block, MAX_BLOCK_ID};
+
+/// 1.12's `Material`, the coarse group a block belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Material {
+    Air,
+    Anvil,
+    Barrier,
+    Cactus,
+    Cake,
+    Carpet,
+    Circuits,
+    Clay,
+    Cloth,
+    CraftedSnow,
+    DragonEgg,
+    Fire,
+    Glass,
+    Gourd,
+    Grass,
+    Ground,
+    Ice,
+    Iron,
+    Lava,
+    Leaves,
+    PackedIce,
+    Piston,
+    Plants,
+    Portal,
+    RedstoneLight,
+    Rock,
+    Sand,
+    Snow,
+    Sponge,
+    StructureVoid,
+    Tnt,
+    Vine,
+    Water,
+    Web,
+    Wood,
+}
+
+const SOLID: u8 = 1;
+const OPAQUE: u8 = 2;
+const LIQUID: u8 = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Properties {
+    material: Material,
+    flags: u8,
+    light_emission: u8,
+    light_opacity: u8,
+    hardness: f32,
+    blast_resistance: f32,
+}
+
+/// Block, material, flags, light emission, light opacity, hardness, blast resistance. Values are
+/// those of the block's default state; hardness is -1 for unbreakable blocks.
+const TABLE: &[(Block, Material, u8, u8, u8, f32, f32)] = &[
+    (Block::Air, Material::Air, 0, 0, 0, 0.0, 0.0),
+    (Block::Stone, Material::Rock, SOLID | OPAQUE, 0, 255, 1.5, 6.0),
+    (Block::Grass, Material::Grass, SOLID | OPAQUE, 0, 255, 0.6, 0.6),
+    (Block::Dirt, Material::Ground, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::Cobblestone, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::Wood, Material::Wood, SOLID | OPAQUE, 0, 255, 2.0, 3.0),
+    (Block::Sapling, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::Bedrock, Material::Rock, SOLID | OPAQUE, 0, 255, -1.0, 3600000.0),
+    (Block::WaterSolid, Material::Water, LIQUID, 0, 3, 100.0, 100.0),
+    (Block::Water, Material::Water, LIQUID, 0, 3, 100.0, 100.0),
+    (Block::LavaSolid, Material::Lava, LIQUID, 15, 0, 100.0, 100.0),
+    (Block::Lava, Material::Lava, LIQUID, 15, 0, 100.0, 100.0),
+    (Block::Sand, Material::Sand, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::Gravel, Material::Sand, SOLID | OPAQUE, 0, 255, 0.6, 0.6),
+    (Block::GoldOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::IronOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::CoalOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::Log, Material::Wood, SOLID | OPAQUE, 0, 255, 2.0, 2.0),
+    (Block::Leaves, Material::Leaves, SOLID, 0, 1, 0.2, 0.2),
+    (Block::Sponge, Material::Sponge, SOLID | OPAQUE, 0, 255, 0.6, 0.6),
+    (Block::Glass, Material::Glass, SOLID, 0, 0, 0.3, 0.3),
+    (Block::LapisOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::LapisBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::Dispenser, Material::Rock, SOLID | OPAQUE, 0, 255, 3.5, 3.5),
+    (Block::Sandstone, Material::Rock, SOLID | OPAQUE, 0, 255, 0.8, 0.8),
+    (Block::NoteBlock, Material::Wood, SOLID | OPAQUE, 0, 255, 0.8, 0.8),
+    (Block::BedBlock, Material::Cloth, SOLID, 0, 0, 0.2, 0.2),
+    (Block::PoweredRail, Material::Circuits, 0, 0, 0, 0.7, 0.7),
+    (Block::DetectorRail, Material::Circuits, 0, 0, 0, 0.7, 0.7),
+    (Block::PistonStickyBase, Material::Piston, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::Web, Material::Web, 0, 0, 1, 4.0, 4.0),
+    (Block::LongGrassBottom, Material::Vine, 0, 0, 0, 0.0, 0.0),
+    (Block::LongGrassUp, Material::Vine, 0, 0, 0, 0.0, 0.0),
+    (Block::PistonBase, Material::Piston, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::PistonExtension, Material::Piston, SOLID, 0, 0, 0.5, 0.5),
+    (Block::Wool, Material::Cloth, SOLID | OPAQUE, 0, 255, 0.8, 0.8),
+    (Block::PistonMovingPiece, Material::Piston, 0, 0, 0, -1.0, 0.0),
+    (Block::YellowFlower, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::RedRose, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::BrownMushroom, Material::Plants, 0, 1, 0, 0.0, 0.0),
+    (Block::RedMushroom, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::GoldBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 3.0, 6.0),
+    (Block::IronBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 5.0, 6.0),
+    (Block::StepDown, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::StepUp, Material::Rock, SOLID, 0, 255, 2.0, 6.0),
+    (Block::Brick, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::Tnt, Material::Tnt, SOLID | OPAQUE, 0, 255, 0.0, 0.0),
+    (Block::Bookshelf, Material::Wood, SOLID | OPAQUE, 0, 255, 1.5, 1.5),
+    (Block::MossyCobblestone, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::Obsidian, Material::Rock, SOLID | OPAQUE, 0, 255, 50.0, 1200.0),
+    (Block::Torch, Material::Circuits, 0, 14, 0, 0.0, 0.0),
+    (Block::Fire, Material::Fire, 0, 15, 0, 0.0, 0.0),
+    (Block::MobSpawner, Material::Rock, SOLID, 0, 0, 5.0, 5.0),
+    (Block::WoodStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::Chest, Material::Wood, SOLID, 0, 0, 2.5, 2.5),
+    (Block::RedstoneWire, Material::Circuits, 0, 0, 0, 0.0, 0.0),
+    (Block::DiamondOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::DiamondBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 5.0, 6.0),
+    (Block::Workbench, Material::Wood, SOLID | OPAQUE, 0, 255, 2.5, 2.5),
+    (Block::Crops, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::Soil, Material::Ground, SOLID, 0, 255, 0.6, 0.6),
+    (Block::FurnaceOff, Material::Rock, SOLID | OPAQUE, 0, 255, 3.5, 3.5),
+    (Block::FurnaceOn, Material::Rock, SOLID | OPAQUE, 13, 255, 3.5, 3.5),
+    (Block::SignPost, Material::Wood, 0, 0, 0, 1.0, 1.0),
+    (Block::WoodenDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::Ladder, Material::Circuits, SOLID, 0, 0, 0.4, 0.4),
+    (Block::Rails, Material::Circuits, 0, 0, 0, 0.7, 0.7),
+    (Block::CobblestoneStairs, Material::Rock, SOLID, 0, 255, 2.0, 6.0),
+    (Block::WallSign, Material::Wood, 0, 0, 0, 1.0, 1.0),
+    (Block::Lever, Material::Circuits, 0, 0, 0, 0.5, 0.5),
+    (Block::StonePlate, Material::Rock, 0, 0, 0, 0.5, 0.5),
+    (Block::IronDoorBlock, Material::Iron, SOLID, 0, 0, 5.0, 5.0),
+    (Block::WoodPlate, Material::Wood, 0, 0, 0, 0.5, 0.5),
+    (Block::RedstoneOreOff, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::RedstoneOreOn, Material::Rock, SOLID | OPAQUE, 9, 255, 3.0, 3.0),
+    (Block::RedstoneTorchOff, Material::Circuits, 0, 0, 0, 0.0, 0.0),
+    (Block::RedstoneTorchOn, Material::Circuits, 0, 7, 0, 0.0, 0.0),
+    (Block::StoneButton, Material::Circuits, 0, 0, 0, 0.5, 0.5),
+    (Block::Snow, Material::Snow, 0, 0, 0, 0.1, 0.1),
+    (Block::Ice, Material::Ice, SOLID, 0, 3, 0.5, 0.5),
+    (Block::SnowBlock, Material::CraftedSnow, SOLID | OPAQUE, 0, 255, 0.2, 0.2),
+    (Block::Cactus, Material::Cactus, SOLID, 0, 0, 0.4, 0.4),
+    (Block::Clay, Material::Clay, SOLID | OPAQUE, 0, 255, 0.6, 0.6),
+    (Block::SugarCaneBlock, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::Jukebox, Material::Wood, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::Fence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::Pumpkin, Material::Gourd, SOLID | OPAQUE, 0, 255, 1.0, 1.0),
+    (Block::Netherrack, Material::Rock, SOLID | OPAQUE, 0, 255, 0.4, 0.4),
+    (Block::SoulSand, Material::Sand, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::Glowstone, Material::Glass, SOLID | OPAQUE, 15, 255, 0.3, 0.3),
+    (Block::Portal, Material::Portal, 0, 11, 0, -1.0, 3600000.0),
+    (Block::JackOLantern, Material::Gourd, SOLID | OPAQUE, 15, 255, 1.0, 1.0),
+    (Block::CakeBlock, Material::Cake, SOLID, 0, 0, 0.5, 0.5),
+    (Block::DiodeBlockOff, Material::Circuits, SOLID, 0, 0, 0.0, 0.0),
+    (Block::DiodeBlockOn, Material::Circuits, SOLID, 0, 0, 0.0, 0.0),
+    (Block::StainedGlass, Material::Glass, SOLID, 0, 0, 0.3, 0.3),
+    (Block::TrapDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::MonsterEggs, Material::Clay, SOLID | OPAQUE, 0, 255, 0.75, 0.75),
+    (Block::SmoothBrick, Material::Rock, SOLID | OPAQUE, 0, 255, 1.5, 6.0),
+    (Block::HugeMushroom1, Material::Wood, SOLID | OPAQUE, 0, 255, 0.2, 0.2),
+    (Block::HugeMushroom2, Material::Wood, SOLID | OPAQUE, 0, 255, 0.2, 0.2),
+    (Block::IronFence, Material::Iron, SOLID, 0, 0, 5.0, 6.0),
+    (Block::ThinGlass, Material::Glass, SOLID, 0, 0, 0.3, 0.3),
+    (Block::MelonBlock, Material::Gourd, SOLID | OPAQUE, 0, 255, 1.0, 1.0),
+    (Block::PumpkinStem, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::MelonStem, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::Vine, Material::Vine, 0, 0, 0, 0.2, 0.2),
+    (Block::FenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::BrickStairs, Material::Rock, SOLID, 0, 255, 2.0, 6.0),
+    (Block::SmoothStairs, Material::Rock, SOLID, 0, 255, 1.5, 6.0),
+    (Block::Mycel, Material::Grass, SOLID | OPAQUE, 0, 255, 0.6, 0.6),
+    (Block::WaterLily, Material::Plants, SOLID, 0, 0, 0.0, 0.0),
+    (Block::NetherBrick, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::NetherFence, Material::Rock, SOLID, 0, 0, 2.0, 6.0),
+    (Block::NetherBrickStairs, Material::Rock, SOLID, 0, 255, 2.0, 6.0),
+    (Block::NetherWarts, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::EnchantmentTable, Material::Rock, SOLID, 0, 0, 5.0, 1200.0),
+    (Block::BrewingStand, Material::Iron, SOLID, 1, 0, 0.5, 0.5),
+    (Block::Cauldron, Material::Iron, SOLID, 0, 0, 2.0, 2.0),
+    (Block::EnderPortal, Material::Portal, 0, 15, 0, -1.0, 3600000.0),
+    (Block::EnderPortalFrame, Material::Rock, SOLID, 1, 0, -1.0, 3600000.0),
+    (Block::EnderStone, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 9.0),
+    (Block::DragonEgg, Material::DragonEgg, SOLID, 1, 0, 3.0, 9.0),
+    (Block::RedstoneLampOff, Material::RedstoneLight, SOLID | OPAQUE, 0, 255, 0.3, 0.3),
+    (Block::RedstoneLampOn, Material::RedstoneLight, SOLID | OPAQUE, 15, 255, 0.3, 0.3),
+    (Block::WoodStepDown, Material::Wood, SOLID | OPAQUE, 0, 255, 2.0, 3.0),
+    (Block::WoodStepUp, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::Cocoa, Material::Plants, SOLID, 0, 0, 0.2, 3.0),
+    (Block::SandstoneStairs, Material::Rock, SOLID, 0, 255, 0.8, 0.8),
+    (Block::EmeraldOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::EnderChest, Material::Rock, SOLID, 7, 0, 22.5, 600.0),
+    (Block::TripwireHook, Material::Circuits, 0, 0, 0, 0.0, 0.0),
+    (Block::Tripwire, Material::Circuits, 0, 0, 0, 0.0, 0.0),
+    (Block::EmeraldBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 5.0, 6.0),
+    (Block::SpruceWoodStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::BirchWoodStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::JungleWoodStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::Command, Material::Iron, SOLID | OPAQUE, 0, 255, -1.0, 3600000.0),
+    (Block::Beacon, Material::Glass, SOLID, 15, 0, 3.0, 3.0),
+    (Block::CobbleWall, Material::Rock, SOLID, 0, 0, 2.0, 6.0),
+    (Block::FlowerPot, Material::Circuits, SOLID, 0, 0, 0.0, 0.0),
+    (Block::Carrot, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::Potato, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::WoodButton, Material::Circuits, 0, 0, 0, 0.5, 0.5),
+    (Block::Anvil, Material::Anvil, SOLID, 0, 0, 5.0, 1200.0),
+    (Block::TrappedChest, Material::Wood, SOLID, 0, 0, 2.5, 2.5),
+    (Block::GoldPlate, Material::Iron, 0, 0, 0, 0.5, 0.5),
+    (Block::IronPlate, Material::Iron, 0, 0, 0, 0.5, 0.5),
+    (Block::RedstoneComparatorOff, Material::Circuits, SOLID, 0, 0, 0.0, 0.0),
+    (Block::RedstoneComparatorOn, Material::Circuits, SOLID, 0, 0, 0.0, 0.0),
+    (Block::DaylightDetectorOff, Material::Wood, SOLID, 0, 0, 0.2, 0.2),
+    (Block::RedstoneBlock, Material::Iron, SOLID | OPAQUE, 0, 255, 5.0, 6.0),
+    (Block::QuartzOre, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::Hopper, Material::Iron, SOLID, 0, 0, 3.0, 4.8),
+    (Block::QuartzBlock, Material::Rock, SOLID | OPAQUE, 0, 255, 0.8, 0.8),
+    (Block::QuartzStairs, Material::Rock, SOLID, 0, 255, 0.8, 0.8),
+    (Block::ActivatorRail, Material::Circuits, 0, 0, 0, 0.7, 0.7),
+    (Block::Dropper, Material::Rock, SOLID | OPAQUE, 0, 255, 3.5, 3.5),
+    (Block::StainedClay, Material::Rock, SOLID | OPAQUE, 0, 255, 1.25, 4.2),
+    (Block::StainedGlassPane, Material::Glass, SOLID, 0, 0, 0.3, 0.3),
+    (Block::Leaves2, Material::Leaves, SOLID, 0, 1, 0.2, 0.2),
+    (Block::Log2, Material::Wood, SOLID | OPAQUE, 0, 255, 2.0, 2.0),
+    (Block::AcaciaStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::DarkOakStairs, Material::Wood, SOLID, 0, 255, 2.0, 3.0),
+    (Block::SlimeBlock, Material::Clay, SOLID, 0, 0, 0.0, 0.0),
+    (Block::Barrier, Material::Barrier, SOLID, 0, 0, -1.0, 3600000.0),
+    (Block::IronTrapdoor, Material::Iron, SOLID, 0, 0, 5.0, 5.0),
+    (Block::Prismarine, Material::Rock, SOLID | OPAQUE, 0, 255, 1.5, 6.0),
+    (Block::SeaLantern, Material::Glass, SOLID | OPAQUE, 15, 255, 0.3, 0.3),
+    (Block::HayBlock, Material::Grass, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::Carpet, Material::Carpet, SOLID, 0, 0, 0.1, 0.1),
+    (Block::HardClay, Material::Rock, SOLID | OPAQUE, 0, 255, 1.25, 4.2),
+    (Block::CoalBlock, Material::Rock, SOLID | OPAQUE, 0, 255, 5.0, 6.0),
+    (Block::PackedIce, Material::PackedIce, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::DoublePlant, Material::Vine, 0, 0, 0, 0.0, 0.0),
+    (Block::StandingBanner, Material::Wood, 0, 0, 0, 1.0, 1.0),
+    (Block::WallBanner, Material::Wood, 0, 0, 0, 1.0, 1.0),
+    (Block::DaylightDetectorOn, Material::Wood, SOLID, 0, 0, 0.2, 0.2),
+    (Block::RedSandstone, Material::Rock, SOLID | OPAQUE, 0, 255, 0.8, 0.8),
+    (Block::RedSandstoneStairs, Material::Rock, SOLID, 0, 255, 0.8, 0.8),
+    (Block::StoneSlab2, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::SpruceFenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::BirchFenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::JungleFenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::DarkOakFenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::AcaciaFenceGate, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::SpruceFence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::BirchFence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::JungleFence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::DarkOakFence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::AcaciaFence, Material::Wood, SOLID, 0, 0, 2.0, 3.0),
+    (Block::SpruceDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::BirchDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::JungleDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::AcaciaDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::DarkOakDoor, Material::Wood, SOLID, 0, 0, 3.0, 3.0),
+    (Block::EndRod, Material::Circuits, SOLID, 14, 0, 0.0, 0.0),
+    (Block::ChorusPlant, Material::Plants, SOLID, 0, 0, 0.4, 0.4),
+    (Block::ChorusFlower, Material::Plants, SOLID, 0, 0, 0.4, 0.4),
+    (Block::PurpurBlock, Material::Rock, SOLID | OPAQUE, 0, 255, 1.5, 6.0),
+    (Block::PurpurPillar, Material::Rock, SOLID | OPAQUE, 0, 255, 1.5, 6.0),
+    (Block::PurpurStairs, Material::Rock, SOLID, 0, 255, 1.5, 6.0),
+    (Block::PurpurSlabBottom, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::PurpurSlabUp, Material::Rock, SOLID, 0, 255, 2.0, 6.0),
+    (Block::EndBricks, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 9.0),
+    (Block::BeetrootBlock, Material::Plants, 0, 0, 0, 0.0, 0.0),
+    (Block::GrassPath, Material::Ground, SOLID, 0, 255, 0.65, 0.65),
+    (Block::EndGateway, Material::Portal, 0, 15, 0, -1.0, 3600000.0),
+    (Block::CommandRepeating, Material::Iron, SOLID | OPAQUE, 0, 255, -1.0, 3600000.0),
+    (Block::CommandChain, Material::Iron, SOLID | OPAQUE, 0, 255, -1.0, 3600000.0),
+    (Block::FrostedIce, Material::Ice, SOLID, 0, 3, 0.5, 0.5),
+    (Block::Magma, Material::Rock, SOLID | OPAQUE, 3, 255, 0.5, 0.5),
+    (Block::NetherWartBlock, Material::Grass, SOLID | OPAQUE, 0, 255, 1.0, 1.0),
+    (Block::RedNetherBrick, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 6.0),
+    (Block::BoneBlock, Material::Rock, SOLID | OPAQUE, 0, 255, 2.0, 2.0),
+    (Block::StructureVoid, Material::StructureVoid, 0, 0, 0, 0.0, 0.0),
+    (Block::Observer, Material::Rock, SOLID | OPAQUE, 0, 255, 3.0, 3.0),
+    (Block::WhiteShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::OrangeShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::MagentaShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::LightBlueShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::YellowShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::LimeShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::PinkShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::GrayShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::SilverShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::CyanShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::PurpleShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::BlueShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::BrownShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::GreenShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::RedShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::BlackShulkerBox, Material::Rock, SOLID, 0, 0, 2.0, 2.0),
+    (Block::WhiteGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::OrangeGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::MagentaGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::LightBlueGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::YellowGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::LimeGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::PinkGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::GrayGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::SilverGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::CyanGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::PurpleGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::BlueGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::BrownGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::GreenGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::RedGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::BlackGlazedTerracotta, Material::Rock, SOLID | OPAQUE, 0, 255, 1.4, 1.4),
+    (Block::Concrete, Material::Rock, SOLID | OPAQUE, 0, 255, 1.8, 1.8),
+    (Block::ConcretePowder, Material::Sand, SOLID | OPAQUE, 0, 255, 0.5, 0.5),
+    (Block::StructureBlock, Material::Iron, SOLID | OPAQUE, 0, 255, -1.0, 3600000.0),
+];
+
+const BY_ID: [Option<Properties>; MAX_BLOCK_ID as usize + 1] = {
+    let mut table = [None; MAX_BLOCK_ID as usize + 1];
+    let mut i = 0;
+    while i < TABLE.len() {
+        let (block, material, flags, light_emission, light_opacity, hardness, blast_resistance) = TABLE[i];
+        table[block as usize] = Some(Properties { material, flags, light_emission, light_opacity, hardness, blast_resistance });
+        i += 1;
+    }
+
+    // Every variant needs a row, checked at compile time so the accessors below can unwrap.
+    let mut i = 0;
+    while i < Block::ALL.len() {
+        assert!(table[Block::ALL[i] as usize].is_some(), "block without properties");
+        i += 1;
+    }
+    table
+};
+
+impl Block {
+    const fn properties(self) -> Properties {
+        match BY_ID[self as usize] {
+            Some(properties) => properties,
+            None => unreachable!(),
+        }
+    }
+
+    pub const fn material(self) -> Material {
+        self.properties().material
+    }
+
+    /// Has a collision box, so entities cannot walk through it.
+    pub const fn is_solid(self) -> bool {
+        self.properties().flags & SOLID != 0
+    }
+
+    /// A full cube that hides the faces of its neighbours and blocks all light.
+    pub const fn is_opaque(self) -> bool {
+        self.properties().flags & OPAQUE != 0
+    }
+
+    pub const fn is_liquid(self) -> bool {
+        self.properties().flags & LIQUID != 0
+    }
+
+    /// Light level the block emits, 0 to 15.
+    pub const fn light_emission(self) -> u8 {
+        self.properties().light_emission
+    }
+
+    /// How much light passing through the block loses, 0 to 255. Stairs, slabs and farmland
+    /// block all light without being opaque.
+    pub const fn light_opacity(self) -> u8 {
+        self.properties().light_opacity
+    }
+
+    /// Negative for blocks that cannot be broken.
+    pub const fn hardness(self) -> f32 {
+        self.properties().hardness
+    }
+
+    pub const fn is_breakable(self) -> bool {
+        self.properties().hardness >= 0.0
+    }
+
+    /// Resistance to explosions as the wiki lists it, e.g. 6 for stone.
+    pub const fn blast_resistance(self) -> f32 {
+        self.properties().blast_resistance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties_are_consistent() {
+        for block in Block::ALL {
+            assert!(!block.is_opaque() || block.is_solid(), "{:?} is opaque but not solid", block);
+            assert!(!block.is_liquid() || !block.is_solid(), "{:?} is liquid and solid", block);
+            assert!(block.light_emission() <= 15, "{:?} emits more than 15", block);
+        }
+    }
+
+    #[test]
+    fn known_blocks() {
+        assert!(!Block::Bedrock.is_breakable());
+        assert_eq!(Block::Glowstone.light_emission(), 15);
+        assert_eq!(Block::Stone.hardness(), 1.5);
+        assert_eq!(Block::Air.light_emission(), 0);
+        assert!(Block::Water.is_liquid());
+    }
+}
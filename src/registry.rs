@@ -0,0 +1,264 @@
+//! Block definitions loaded at runtime, so worlds with modded ids the [`Block`] enum does not know
+//! can still be loaded. The built-in blocks are the default definitions, a JSON file adds to or
+//! overrides them.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use get_size::GetSize;
+
+use crate::block::{Block, BlockState};
+use crate::properties::Material;
+
+/// Highest id a registry accepts, the 12 bits of a 1.12 block id.
+pub const MAX_REGISTRY_ID: u16 = 4095;
+
+/// A block state by raw id, known to some [`BlockRegistry`] rather than to [`Block`].
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, GetSize, serde::Serialize, serde::Deserialize)]
+pub struct RegistryState {
+    pub id: u16,
+    pub data: u8,
+}
+
+impl RegistryState {
+    /// `data` is masked to its 4 bits.
+    pub const fn new(id: u16, data: u8) -> Self {
+        Self { id, data: data & 0x0f }
+    }
+
+    /// `None` for ids that only the registry knows about.
+    pub fn to_legacy(self) -> Option<BlockState> {
+        Block::from_id(self.id).map(|block| BlockState::new(block, self.data))
+    }
+}
+
+impl From<BlockState> for RegistryState {
+    fn from(state: BlockState) -> Self {
+        Self { id: state.block.id(), data: state.data }
+    }
+}
+
+/// What [`Block`]'s const accessors report for built-in blocks. Fields missing from JSON default
+/// to an unlit, non-solid, instantly broken block.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct BlockProperties {
+    pub material: Option<Material>,
+    pub solid: bool,
+    pub opaque: bool,
+    pub liquid: bool,
+    pub light_emission: u8,
+    pub light_opacity: u8,
+    /// Negative for unbreakable blocks.
+    pub hardness: f32,
+    pub blast_resistance: f32,
+}
+
+impl From<Block> for BlockProperties {
+    fn from(block: Block) -> Self {
+        Self {
+            material: Some(block.material()),
+            solid: block.is_solid(),
+            opaque: block.is_opaque(),
+            liquid: block.is_liquid(),
+            light_emission: block.light_emission(),
+            light_opacity: block.light_opacity(),
+            hardness: block.hardness(),
+            blast_resistance: block.blast_resistance(),
+        }
+    }
+}
+
+/// One entry of a registry file, e.g. `{"id": 300, "name": "mod:ore", "hardness": 3.0}`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BlockDefinition {
+    pub id: u16,
+    pub name: String,
+    #[serde(flatten)]
+    pub properties: BlockProperties,
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    IdOutOfRange(u16),
+    /// The same id is defined twice in one file.
+    DuplicateId(u16),
+    /// Two ids share a name.
+    DuplicateName(String),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Json(e) => write!(f, "json error: {}", e),
+            Self::IdOutOfRange(id) => write!(f, "block id {} is above {}", id, MAX_REGISTRY_ID),
+            Self::DuplicateId(id) => write!(f, "block id {} is defined twice", id),
+            Self::DuplicateName(name) => write!(f, "block name {} is used by two ids", name),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RegistryError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for RegistryError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockRegistry {
+    definitions: BTreeMap<u16, BlockDefinition>,
+    names: HashMap<String, u16>,
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::vanilla()
+    }
+}
+
+impl BlockRegistry {
+    /// Every [`Block`], named like its [`Display`].
+    pub fn vanilla() -> Self {
+        let mut registry = Self { definitions: BTreeMap::new(), names: HashMap::new() };
+        for block in Block::ALL {
+            let definition = BlockDefinition { id: block.id(), name: block.to_string(), properties: block.into() };
+            registry.insert(definition).expect("built-in blocks have unique ids and names");
+        }
+        registry
+    }
+
+    /// Adds `definition`, returning the one it replaces under the same id.
+    pub fn insert(&mut self, definition: BlockDefinition) -> Result<Option<BlockDefinition>, RegistryError> {
+        if definition.id > MAX_REGISTRY_ID {
+            return Err(RegistryError::IdOutOfRange(definition.id));
+        }
+        if self.names.get(&definition.name).is_some_and(|id| *id != definition.id) {
+            return Err(RegistryError::DuplicateName(definition.name));
+        }
+
+        if let Some(old) = self.definitions.get(&definition.id) {
+            self.names.remove(&old.name);
+        }
+        self.names.insert(definition.name.clone(), definition.id);
+        Ok(self.definitions.insert(definition.id, definition))
+    }
+
+    /// The vanilla registry plus a JSON array of [`BlockDefinition`]s, which may override vanilla
+    /// ids.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, RegistryError> {
+        let definitions: Vec<BlockDefinition> = serde_json::from_slice(bytes)?;
+        let mut registry = Self::vanilla();
+        let mut seen = HashSet::new();
+        for definition in definitions {
+            if !seen.insert(definition.id) {
+                return Err(RegistryError::DuplicateId(definition.id));
+            }
+            registry.insert(definition)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        Self::from_json(&std::fs::read(path)?)
+    }
+
+    /// Every definition as a JSON array, readable by [`BlockRegistry::from_json`].
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(&self.definitions.values().collect::<Vec<_>>()).unwrap()
+    }
+
+    pub fn get(&self, id: u16) -> Option<&BlockDefinition> {
+        self.definitions.get(&id)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&BlockDefinition> {
+        self.names.get(name).map(|id| &self.definitions[id])
+    }
+
+    pub fn contains(&self, id: u16) -> bool {
+        self.definitions.contains_key(&id)
+    }
+
+    /// The state for `id`, `None` if the registry does not define it.
+    pub fn state(&self, id: u16, data: u8) -> Option<RegistryState> {
+        self.contains(id).then(|| RegistryState::new(id, data))
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item=&BlockDefinition> {
+        self.definitions.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODDED: &str = r#"[
+        {"id": 300, "name": "mod:ore", "hardness": 3.0, "solid": true},
+        {"id": 1, "name": "mod:stone"}
+    ]"#;
+
+    #[test]
+    fn vanilla() {
+        let registry = BlockRegistry::vanilla();
+        assert_eq!(registry.len(), Block::ALL.len());
+        assert!(!registry.is_empty());
+        assert_eq!(registry.by_name(&Block::Stone.to_string()).map(|d| d.id), Some(Block::Stone.id()));
+        assert_eq!(registry.get(Block::Glowstone.id()).unwrap().properties, BlockProperties::from(Block::Glowstone));
+        assert_eq!(registry.state(Block::Wool.id(), 0x1e), Some(RegistryState::new(Block::Wool.id(), 14)));
+        assert_eq!(RegistryState::new(Block::Wool.id(), 14).to_legacy(), Some(BlockState::new(Block::Wool, 14)));
+    }
+
+    #[test]
+    fn json() {
+        let registry = BlockRegistry::from_json(MODDED.as_bytes()).unwrap();
+        assert_eq!(registry.len(), Block::ALL.len() + 1);
+        let ore = registry.by_name("mod:ore").unwrap();
+        assert_eq!((ore.id, ore.properties.hardness, ore.properties.solid, ore.properties.opaque), (300, 3.0, true, false));
+        assert_eq!(registry.get(1).unwrap().name, "mod:stone");
+        assert!(registry.by_name(&Block::Stone.to_string()).is_none());
+        assert_eq!(RegistryState::new(300, 0).to_legacy(), None);
+
+        let path = crate::tests::temp_dir("registry-json").join("blocks.json");
+        std::fs::write(&path, registry.to_json()).unwrap();
+        let loaded = BlockRegistry::load(&path).unwrap();
+        assert!(loaded.definitions().eq(registry.definitions()));
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let json = |definitions: &str| BlockRegistry::from_json(definitions.as_bytes());
+        assert!(matches!(json(r#"[{"id": 4096, "name": "mod:a"}]"#), Err(RegistryError::IdOutOfRange(4096))));
+        assert!(matches!(json(r#"[{"id": 300, "name": "mod:a"}, {"id": 300, "name": "mod:b"}]"#), Err(RegistryError::DuplicateId(300))));
+        assert!(matches!(json(r#"[{"id": 300, "name": "mod:a"}, {"id": 301, "name": "mod:a"}]"#), Err(RegistryError::DuplicateName(_))));
+        assert!(matches!(json(r#"{"id": 300}"#), Err(RegistryError::Json(_))));
+    }
+}
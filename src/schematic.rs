@@ -0,0 +1,168 @@
+//! MCEdit `.schematic` files: a gzipped NBT compound with the blocks of a cuboid in yzx order.
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::{Block, BlockPosition, BlockState};
+use crate::anvil::{nibble, pack_nibbles};
+use crate::nbt::{compound, NbtError, Tag};
+use crate::volume::Volume;
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(std::io::Error),
+    Nbt(NbtError),
+    MissingTag(&'static str),
+    /// Only `Alpha` uses the numeric ids of `Block`.
+    UnsupportedMaterials(String),
+    /// `Blocks`, `AddBlocks` or `Data` does not match Width × Height × Length.
+    WrongLength { expected: usize, actual: usize },
+    UnknownBlock { id: u16, position: BlockPosition },
+}
+
+impl Display for SchematicError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Nbt(e) => write!(f, "nbt error: {}", e),
+            Self::MissingTag(name) => write!(f, "missing or mistyped tag {}", name),
+            Self::UnsupportedMaterials(m) => write!(f, "unsupported materials {:?}", m),
+            Self::WrongLength { expected, actual } => write!(f, "expected {} blocks, got {}", expected, actual),
+            Self::UnknownBlock { id, position } => write!(f, "unknown block id {} at {:?}", id, position),
+        }
+    }
+}
+
+impl std::error::Error for SchematicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Nbt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SchematicError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<NbtError> for SchematicError {
+    fn from(e: NbtError) -> Self {
+        Self::Nbt(e)
+    }
+}
+
+impl Volume {
+    /// A missing `Data` reads as all zeros.
+    pub fn from_schematic(root: &Tag) -> Result<Self, SchematicError> {
+        let dimension = |name| root.get(name).and_then(Tag::as_i64).map(|v| v as u16).ok_or(SchematicError::MissingTag(name));
+        let (width, height, length) = (dimension("Width")?, dimension("Height")?, dimension("Length")?);
+
+        match root.get("Materials").and_then(Tag::as_str) {
+            None | Some("Alpha") => {}
+            Some(m) => return Err(SchematicError::UnsupportedMaterials(m.to_string())),
+        }
+
+        let ids = root.get("Blocks").and_then(Tag::as_byte_array).ok_or(SchematicError::MissingTag("Blocks"))?;
+        let add = root.get("AddBlocks").and_then(Tag::as_byte_array);
+        let data = root.get("Data").and_then(Tag::as_byte_array);
+        let expected = width as usize * height as usize * length as usize;
+        if ids.len() != expected {
+            return Err(SchematicError::WrongLength { expected, actual: ids.len() });
+        }
+        if let Some(add) = add.filter(|a| a.len() != expected.div_ceil(2)) {
+            return Err(SchematicError::WrongLength { expected: expected.div_ceil(2), actual: add.len() });
+        }
+        if let Some(data) = data.filter(|d| d.len() != expected) {
+            return Err(SchematicError::WrongLength { expected, actual: data.len() });
+        }
+
+        let blocks = ids.iter().enumerate()
+            .map(|(i, id)| {
+                let id = *id as u16 | add.map_or(0, |a| (nibble(a, i) as u16) << 8);
+                let block = Block::from_id(id).ok_or_else(|| {
+                    let (x, z, y) = (i % width as usize, i / width as usize % length as usize, i / width as usize / length as usize);
+                    SchematicError::UnknownBlock { id, position: BlockPosition::new(x as i32, y as u32, z as i32) }
+                })?;
+                Ok(BlockState::new(block, data.map_or(0, |d| d[i])))
+            })
+            .collect::<Result<Vec<_>, SchematicError>>()?;
+
+        Ok(Volume::new(width, height, length, &blocks))
+    }
+
+    pub fn read_schematic(path: impl AsRef<Path>) -> Result<Self, SchematicError> {
+        Self::from_schematic(&crate::nbt::read_compressed(&std::fs::read(path)?)?.1)
+    }
+
+    pub fn to_schematic(&self) -> Tag {
+        let blocks = self.blocks();
+        let ids = blocks.iter().map(|b| b.block.id()).collect::<Vec<_>>();
+        let mut root = compound([
+            ("Width", Tag::Short(self.width() as i16)),
+            ("Height", Tag::Short(self.height() as i16)),
+            ("Length", Tag::Short(self.length() as i16)),
+            ("Materials", Tag::String("Alpha".to_string())),
+            ("Blocks", Tag::ByteArray(ids.iter().map(|id| *id as u8).collect())),
+            ("Data", Tag::ByteArray(blocks.iter().map(|b| b.data).collect())),
+            ("Entities", Tag::List(vec![])),
+            ("TileEntities", Tag::List(vec![])),
+        ]);
+
+        if ids.iter().any(|id| *id > 0xff) {
+            // Pad to an even count so the last block gets its nibble.
+            let high = ids.iter().map(|id| (id >> 8) as u8).chain((ids.len() % 2 == 1).then_some(0));
+            if let Tag::Compound(c) = &mut root {
+                c.insert("AddBlocks".to_string(), Tag::ByteArray(pack_nibbles(high)));
+            }
+        }
+        root
+    }
+
+    pub fn write_schematic(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, crate::nbt::write_gzip("Schematic", &self.to_schematic()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume() -> Volume {
+        let blocks: Vec<BlockState> = (0..3 * 2 * 5).map(|i| BlockState::new(Block::Wool, (i % 16) as u8)).collect();
+        Volume::new(3, 2, 5, &blocks)
+    }
+
+    fn with(name: &str, tag: Tag) -> Tag {
+        let Tag::Compound(mut root) = volume().to_schematic() else { unreachable!() };
+        root.insert(name.to_string(), tag);
+        Tag::Compound(root)
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(Volume::from_schematic(&volume().to_schematic()).unwrap().blocks(), volume().blocks());
+
+        let path = crate::tests::temp_dir("schematic-round-trip").join("volume.schematic");
+        volume().write_schematic(&path).unwrap();
+        let read = Volume::read_schematic(&path).unwrap();
+        assert_eq!((read.width(), read.height(), read.length()), (3, 2, 5));
+        assert_eq!(read.blocks(), volume().blocks());
+    }
+
+    #[test]
+    fn rejects_malformed_schematics() {
+        assert!(matches!(Volume::from_schematic(&with("Materials", Tag::String("Pocket".to_string()))), Err(SchematicError::UnsupportedMaterials(_))));
+        assert!(matches!(Volume::from_schematic(&with("Data", Tag::ByteArray(vec![0; 29]))), Err(SchematicError::WrongLength { expected: 30, actual: 29 })));
+        assert!(matches!(Volume::from_schematic(&with("AddBlocks", Tag::ByteArray(vec![0; 30]))), Err(SchematicError::WrongLength { expected: 15, actual: 30 })));
+        assert!(matches!(Volume::from_schematic(&with("Width", Tag::String("3".to_string()))), Err(SchematicError::MissingTag("Width"))));
+
+        let mut add = vec![0; 15];
+        add[7] = 0x10;
+        assert!(matches!(Volume::from_schematic(&with("AddBlocks", Tag::ByteArray(add))), Err(SchematicError::UnknownBlock { id: 291, position })
+            if position == BlockPosition::new(0, 1, 0)));
+    }
+}
@@ -0,0 +1,193 @@
+//! Sponge `.schem` files, versions 1 and 2: a palette of block state strings and one varint
+//! palette index per block in yzx order.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::BlockState;
+use crate::codec::{read_varint, write_varint};
+use crate::flattening::{flat_name, parse_flat_name};
+use crate::nbt::{compound, NbtError, Tag};
+use crate::volume::Volume;
+
+pub const VERSION: i32 = 2;
+/// Data version of 1.13.2, the first release whose names the palette uses.
+pub const DATA_VERSION: i32 = 1631;
+
+#[derive(Debug)]
+pub enum SpongeError {
+    Io(std::io::Error),
+    Nbt(NbtError),
+    MissingTag(&'static str),
+    UnsupportedVersion(i64),
+    /// `BlockData` does not hold Width × Height × Length indices.
+    WrongLength { expected: usize, actual: usize },
+    /// A varint runs past the end of `BlockData` or over 32 bits.
+    BadVarint,
+    /// `BlockData` refers to an index the palette does not define.
+    UnknownIndex(u32),
+    /// A palette entry with an index that is negative or not below `PaletteMax` and the number
+    /// of entries.
+    BadPaletteIndex(i64),
+}
+
+impl Display for SpongeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Nbt(e) => write!(f, "nbt error: {}", e),
+            Self::MissingTag(name) => write!(f, "missing or mistyped tag {}", name),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            Self::WrongLength { expected, actual } => write!(f, "expected {} blocks, got {}", expected, actual),
+            Self::BadVarint => f.write_str("malformed varint in block data"),
+            Self::UnknownIndex(i) => write!(f, "palette index {} is not defined", i),
+            Self::BadPaletteIndex(i) => write!(f, "palette entry has out of range index {}", i),
+        }
+    }
+}
+
+impl std::error::Error for SpongeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Nbt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SpongeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<NbtError> for SpongeError {
+    fn from(e: NbtError) -> Self {
+        Self::Nbt(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct SpongeImport {
+    pub volume: Volume,
+    /// Palette entries with no legacy equivalent. Blocks using them were imported as air.
+    pub unmapped: BTreeSet<String>,
+}
+
+impl Volume {
+    pub fn from_sponge(root: &Tag) -> Result<SpongeImport, SpongeError> {
+        match root.get("Version").and_then(Tag::as_i64) {
+            Some(1 | 2) => {}
+            Some(v) => return Err(SpongeError::UnsupportedVersion(v)),
+            None => return Err(SpongeError::MissingTag("Version")),
+        }
+
+        let dimension = |name| root.get(name).and_then(Tag::as_i64).map(|v| v as u16).ok_or(SpongeError::MissingTag(name));
+        let (width, height, length) = (dimension("Width")?, dimension("Height")?, dimension("Length")?);
+
+        let entries = root.get("Palette").and_then(Tag::as_compound).ok_or(SpongeError::MissingTag("Palette"))?;
+        let max = root.get("PaletteMax").and_then(Tag::as_i64).map_or(entries.len(), |max| (max.max(0) as usize).min(entries.len()));
+
+        let mut unmapped = BTreeSet::new();
+        let mut palette = vec![None; max];
+        for (state, index) in entries {
+            let index = index.as_i64().ok_or(SpongeError::MissingTag("Palette"))?;
+            let slot = usize::try_from(index).ok().and_then(|i| palette.get_mut(i)).ok_or(SpongeError::BadPaletteIndex(index))?;
+            *slot = Some(parse_flat_name(state).unwrap_or_else(|| {
+                unmapped.insert(state.clone());
+                BlockState::AIR
+            }));
+        }
+
+        let data = root.get("BlockData").and_then(Tag::as_byte_array).ok_or(SpongeError::MissingTag("BlockData"))?;
+        let mut r = data;
+        let mut indices = vec![];
+        while !r.is_empty() {
+            indices.push(read_varint(&mut r).ok_or(SpongeError::BadVarint)?);
+        }
+        let expected = width as usize * height as usize * length as usize;
+        if indices.len() != expected {
+            return Err(SpongeError::WrongLength { expected, actual: indices.len() });
+        }
+
+        let blocks = indices.iter()
+            .map(|i| palette.get(*i as usize).copied().flatten().ok_or(SpongeError::UnknownIndex(*i)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SpongeImport { volume: Volume::new(width, height, length, &blocks), unmapped })
+    }
+
+    pub fn read_sponge(path: impl AsRef<Path>) -> Result<SpongeImport, SpongeError> {
+        Self::from_sponge(&crate::nbt::read_compressed(&std::fs::read(path)?)?.1)
+    }
+
+    /// Palette entries whose states flatten to the same name share one index.
+    pub fn to_sponge(&self) -> Tag {
+        let mut names = BTreeMap::new();
+        let remap: HashMap<u16, u32> = self.palette().iter()
+            .map(|(index, block)| {
+                let len = names.len() as u32;
+                (*index, *names.entry(flat_name(*block)).or_insert(len))
+            })
+            .collect();
+        let palette: BTreeMap<String, Tag> = names.into_iter().map(|(name, index)| (name, Tag::Int(index as i32))).collect();
+
+        let mut data = vec![];
+        self.indices().into_iter().for_each(|i| write_varint(&mut data, remap[&i]));
+
+        compound([
+            ("Version", Tag::Int(VERSION)),
+            ("DataVersion", Tag::Int(DATA_VERSION)),
+            ("Width", Tag::Short(self.width() as i16)),
+            ("Height", Tag::Short(self.height() as i16)),
+            ("Length", Tag::Short(self.length() as i16)),
+            ("Offset", Tag::IntArray(vec![0, 0, 0])),
+            ("PaletteMax", Tag::Int(palette.len() as i32)),
+            ("Palette", Tag::Compound(palette)),
+            ("BlockData", Tag::ByteArray(data)),
+            ("BlockEntities", Tag::List(vec![])),
+        ])
+    }
+
+    pub fn write_sponge(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, crate::nbt::write_gzip("Schematic", &self.to_sponge()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    fn volume() -> Volume {
+        let blocks = [Block::Stone.into(), BlockState::new(Block::Wool, 14), BlockState::AIR, Block::Stone.into()];
+        Volume::new(2, 1, 2, &blocks)
+    }
+
+    fn with_palette_index(index: i32) -> Tag {
+        let Tag::Compound(mut root) = volume().to_sponge() else { unreachable!() };
+        let Some(Tag::Compound(palette)) = root.get_mut("Palette") else { unreachable!() };
+        *palette.values_mut().next().unwrap() = Tag::Int(index);
+        Tag::Compound(root)
+    }
+
+    #[test]
+    fn round_trip() {
+        let import = Volume::from_sponge(&volume().to_sponge()).unwrap();
+        assert!(import.unmapped.is_empty());
+        assert_eq!(import.volume.blocks(), volume().blocks());
+
+        let path = crate::tests::temp_dir("sponge-round-trip").join("volume.schem");
+        volume().write_sponge(&path).unwrap();
+        assert_eq!(Volume::read_sponge(&path).unwrap().volume.blocks(), volume().blocks());
+    }
+
+    #[test]
+    fn rejects_out_of_range_palette_indices() {
+        for index in [-1, i32::MAX, 3] {
+            assert!(matches!(Volume::from_sponge(&with_palette_index(index)), Err(SpongeError::BadPaletteIndex(i)) if i == index as i64));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Free-standing cuboids of blocks, such as schematics, stored as a grid of 16³ octrees.
+
+use std::fmt::{Display, Formatter};
+
+use bimap::BiBTreeMap;
+
+use crate::{palette_index, BlockPosition, BlockState, ChunkData, ChunkPosition, LocalBlockPosition, Node};
+use crate::world::{World, WORLD_HEIGHT};
+
+/// Most blocks [`World::copy`] takes in one selection, about 43,000 chunks of full world height.
+pub const MAX_BLOCKS: u64 = 1 << 32;
+
+/// A selection with a side longer than the 65535 blocks a [`Volume`] holds, or with more than
+/// [`MAX_BLOCKS`] blocks in total.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VolumeTooLarge {
+    pub width: u64,
+    pub height: u64,
+    pub length: u64,
+}
+
+impl Display for VolumeTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "selection of {}x{}x{} has a side longer than {} blocks or more than {} blocks in total",
+               self.width, self.height, self.length, u16::MAX, MAX_BLOCKS)
+    }
+}
+
+impl std::error::Error for VolumeTooLarge {}
+
+#[derive(Debug)]
+pub struct Volume {
+    width: u16,
+    height: u16,
+    length: u16,
+    // yzx order, the cells on the far edges are padded with air
+    cells: Vec<Node<u16>>,
+    // up to 16 data values per block are more states than u8 indices cover
+    palette: BiBTreeMap<u16, BlockState>,
+}
+
+impl Volume {
+    /// `blocks` are in yzx order, `(y * length + z) * width + x`.
+    pub fn new(width: u16, height: u16, length: u16, blocks: &[BlockState]) -> Self {
+        let (w, h, l) = (width as usize, height as usize, length as usize);
+        assert_eq!(blocks.len(), w * h * l, "volume of {}x{}x{} needs as many blocks", w, h, l);
+
+        let mut palette = BiBTreeMap::new();
+        palette.insert(0, BlockState::AIR);
+        for block in blocks {
+            if !palette.contains_right(block) {
+                palette.insert(palette.len() as u16, *block);
+            }
+        }
+
+        let mut cells = vec![];
+        for cy in 0..h.div_ceil(16) {
+            for cz in 0..l.div_ceil(16) {
+                for cx in 0..w.div_ceil(16) {
+                    let section = std::array::from_fn(|i| {
+                        let (x, y, z) = (cx * 16 + (i & 0x0f), cy * 16 + (i >> 8), cz * 16 + ((i >> 4) & 0x0f));
+                        if x < w && y < h && z < l { *palette.get_by_right(&blocks[(y * l + z) * w + x]).unwrap() } else { 0 }
+                    });
+                    cells.push(Node::new(section));
+                }
+            }
+        }
+
+        Self { width, height, length, cells, palette }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn palette(&self) -> &BiBTreeMap<u16, BlockState> {
+        &self.palette
+    }
+
+    /// Palette index of the block at the given position.
+    pub fn get_index(&self, x: u16, y: u16, z: u16) -> Option<u16> {
+        if x >= self.width || y >= self.height || z >= self.length {
+            return None;
+        }
+        let (cells_x, cells_z) = (self.width.div_ceil(16) as usize, self.length.div_ceil(16) as usize);
+        let cell = &self.cells[((y >> 4) as usize * cells_z + (z >> 4) as usize) * cells_x + (x >> 4) as usize];
+        Some(cell.get(LocalBlockPosition::new((x & 0x0f) as u32, (y & 0x0f) as u32, (z & 0x0f) as u32)))
+    }
+
+    pub fn get(&self, x: u16, y: u16, z: u16) -> Option<BlockState> {
+        self.get_index(x, y, z).map(|id| *self.palette.get_by_left(&id).unwrap())
+    }
+
+    /// Palette index of every block in yzx order.
+    pub fn indices(&self) -> Vec<u16> {
+        (0..self.height)
+            .flat_map(|y| (0..self.length).flat_map(move |z| (0..self.width).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| self.get_index(x, y, z).unwrap())
+            .collect()
+    }
+
+    /// Every block in yzx order, the layout [`Volume::new`] takes.
+    pub fn blocks(&self) -> Vec<BlockState> {
+        self.indices().into_iter().map(|id| *self.palette.get_by_left(&id).unwrap()).collect()
+    }
+}
+
+impl ChunkData {
+    /// Copies `volume` into the chunk with its minimum corner at the chunk-relative `origin`.
+    /// Parts outside the chunk are clipped, air in the volume overwrites. Every uniform cube of the
+    /// volume's cells is written with [`ChunkData::fill`], so only the touched sections change.
+    pub fn paste(&mut self, volume: &Volume, origin: BlockPosition) {
+        let origin = [origin.x as i64, origin.y as i64, origin.z as i64];
+        let size = [volume.width, volume.height, volume.length].map(|side| side as i64);
+        let chunk = [16, WORLD_HEIGHT as i64, 16];
+        // inclusive bounds of the pasted blocks, relative to the volume
+        let lo: [i64; 3] = std::array::from_fn(|i| (-origin[i]).max(0));
+        let hi: [i64; 3] = std::array::from_fn(|i| (chunk[i] - origin[i]).min(size[i]) - 1);
+        if (0..3).any(|i| lo[i] > hi[i]) {
+            return;
+        }
+
+        let cells = size.map(|side| (side + 15) >> 4);
+        for cy in lo[1] >> 4..=hi[1] >> 4 {
+            for cz in lo[2] >> 4..=hi[2] >> 4 {
+                for cx in lo[0] >> 4..=hi[0] >> 4 {
+                    let cell = &volume.cells[((cy * cells[2] + cz) * cells[0] + cx) as usize];
+                    for (pos, side, id) in cell.leaves() {
+                        let cube = [cx * 16 + ((pos.c >> 8) & 0x0f) as i64, cy * 16 + (pos.c >> 16) as i64, cz * 16 + (pos.c & 0x0f) as i64];
+                        let min: [i64; 3] = std::array::from_fn(|i| cube[i].max(lo[i]) + origin[i]);
+                        let max: [i64; 3] = std::array::from_fn(|i| (cube[i] + side as i64 - 1).min(hi[i]) + origin[i]);
+                        if (0..3).all(|i| min[i] <= max[i]) {
+                            let block = *volume.palette.get_by_left(&id).unwrap();
+                            self.fill(LocalBlockPosition::new(min[0] as u32, min[1] as u32, min[2] as u32),
+                                      LocalBlockPosition::new(max[0] as u32, max[1] as u32, max[2] as u32), block);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl World {
+    /// Pastes `volume` with its minimum corner at `origin`, creating air chunks where none are loaded.
+    pub fn paste(&mut self, volume: &Volume, origin: BlockPosition) {
+        let max = BlockPosition::new(origin.x + volume.width as i32 - 1, 0, origin.z + volume.length as i32 - 1);
+        for cx in origin.x >> 4..=max.x >> 4 {
+            for cz in origin.z >> 4..=max.z >> 4 {
+                let pos = ChunkPosition::new(cx, cz);
+                let local = BlockPosition::new(origin.x - cx * 16, origin.y as u32, origin.z - cz * 16);
+                match self.chunk_mut(pos) {
+                    Some(chunk) => chunk.paste(volume, local),
+                    None => {
+                        let mut chunk = ChunkData::empty();
+                        chunk.paste(volume, local);
+                        self.insert(pos, chunk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the cuboid spanned by the two corners, inclusive. Unloaded chunks read as air. Each
+    /// 16³ cell of the volume is built from the chunk sections it overlaps, so the selection is never
+    /// expanded into one flat buffer.
+    pub fn copy(&self, a: BlockPosition, b: BlockPosition) -> Result<Volume, VolumeTooLarge> {
+        let min = [a.x.min(b.x) as i64, a.y.min(b.y) as i64, a.z.min(b.z) as i64];
+        let max = [a.x.max(b.x) as i64, a.y.max(b.y) as i64, a.z.max(b.z) as i64];
+        let side = |i: usize| (max[i] - min[i] + 1) as u64;
+        let size = VolumeTooLarge { width: side(0), height: side(1), length: side(2) };
+        if [size.width, size.height, size.length].iter().any(|side| *side > u16::MAX as u64)
+            || size.width * size.height * size.length > MAX_BLOCKS {
+            return Err(size);
+        }
+        let (width, height, length) = (size.width as u16, size.height as u16, size.length as u16);
+
+        let mut palette = BiBTreeMap::new();
+        palette.insert(0, BlockState::AIR);
+        let mut cells = vec![];
+        for cy in 0..height.div_ceil(16) as i64 {
+            for cz in 0..length.div_ceil(16) as i64 {
+                for cx in 0..width.div_ceil(16) as i64 {
+                    // world bounds of the cell, clipped to the selection
+                    let lo = [min[0] + cx * 16, min[1] + cy * 16, min[2] + cz * 16];
+                    let hi: [i64; 3] = std::array::from_fn(|i| (lo[i] + 15).min(max[i]));
+
+                    let mut section = [0; 16 * 16 * 16];
+                    for chunk_x in lo[0] >> 4..=hi[0] >> 4 {
+                        for chunk_z in lo[2] >> 4..=hi[2] >> 4 {
+                            let Some(chunk) = self.chunk(ChunkPosition::new(chunk_x as i32, chunk_z as i32)) else { continue };
+                            for y in lo[1] >> 4..=(hi[1] >> 4).min(WORLD_HEIGHT as i64 / 16 - 1) {
+                                let raw = chunk.to_raw_section(y as usize);
+                                for (i, block) in raw.iter().enumerate() {
+                                    let pos = [chunk_x * 16 + (i & 0x0f) as i64, y * 16 + (i >> 8) as i64, chunk_z * 16 + ((i >> 4) & 0x0f) as i64];
+                                    if (0..3).all(|i| lo[i] <= pos[i] && pos[i] <= hi[i]) {
+                                        let (x, y, z) = ((pos[0] - lo[0]) as usize, (pos[1] - lo[1]) as usize, (pos[2] - lo[2]) as usize);
+                                        section[x + (z << 4) + (y << 8)] = palette_index(&mut palette, *block);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    cells.push(Node::new(section));
+                }
+            }
+        }
+
+        Ok(Volume { width, height, length, cells, palette })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    fn volume() -> Volume {
+        let blocks: Vec<BlockState> = (0..20 * 3 * 18).map(|i| if i % 3 == 0 { Block::Stone.into() } else { Block::Dirt.into() }).collect();
+        Volume::new(20, 3, 18, &blocks)
+    }
+
+    #[test]
+    fn get() {
+        let volume = volume();
+        assert_eq!(volume.get(0, 0, 0), Some(Block::Stone.into()));
+        assert_eq!(volume.get(19, 2, 17), Some(volume.blocks()[20 * 3 * 18 - 1]));
+        assert_eq!(volume.get(20, 0, 0), None);
+        assert_eq!(volume.get_index(0, 3, 0), None);
+        assert_eq!(volume.palette().len(), 3);
+    }
+
+    #[test]
+    fn paste_and_copy() {
+        let mut world = World::new();
+        // spans two sections as well as four chunks
+        let origin = BlockPosition::new(-5, 110, 30);
+        world.paste(&volume(), origin);
+        assert_eq!(world.len(), 4);
+        assert_eq!(world.get(BlockPosition::new(-5 + 19, 112, 30 + 17)), volume().get(19, 2, 17));
+        assert_eq!(world.get(BlockPosition::new(-6, 110, 30)), Some(BlockState::AIR));
+
+        let copy = world.copy(BlockPosition::new(-5 + 19, 112, 30 + 17), origin).unwrap();
+        assert_eq!(copy.blocks(), volume().blocks());
+    }
+
+    #[test]
+    fn paste_overwrites_and_clips() {
+        let mut chunk = ChunkData::empty();
+        chunk.fill(LocalBlockPosition::new(0, 0, 0), LocalBlockPosition::new(15, 40, 15), Block::Stone.into());
+        let blocks: Vec<BlockState> = (0..4 * 20 * 4).map(|i| if i % 2 == 0 { BlockState::AIR } else { Block::Dirt.into() }).collect();
+        let volume = Volume::new(4, 20, 4, &blocks);
+
+        chunk.paste(&volume, BlockPosition::new(14, 30, -2));
+        assert_eq!(chunk.get(LocalBlockPosition::new(14, 30, 0)), volume.get(0, 0, 2).unwrap());
+        assert_eq!(chunk.get(LocalBlockPosition::new(15, 49, 1)), volume.get(1, 19, 3).unwrap());
+        assert_eq!(chunk.get(LocalBlockPosition::new(13, 30, 0)), Block::Stone.into());
+        assert_eq!(chunk.get(LocalBlockPosition::new(14, 30, 2)), Block::Stone.into());
+
+        chunk.paste(&volume, BlockPosition::new(0, WORLD_HEIGHT - 2, 0));
+        assert_eq!(chunk.get(LocalBlockPosition::new(3, WORLD_HEIGHT - 1, 3)), volume.get(3, 1, 3).unwrap());
+    }
+
+    #[test]
+    fn copy_rejects_oversized_selections() {
+        let world = World::new();
+        assert_eq!(world.copy(BlockPosition::new(0, 0, 0), BlockPosition::new(65535, 0, 0)).unwrap_err(),
+                   VolumeTooLarge { width: 65536, height: 1, length: 1 });
+        assert_eq!(world.copy(BlockPosition::new(0, 0, i32::MIN), BlockPosition::new(0, 65535, i32::MAX)).unwrap_err(),
+                   VolumeTooLarge { width: 1, height: 65536, length: 1 << 32 });
+        assert_eq!(world.copy(BlockPosition::new(0, 0, 0), BlockPosition::new(65534, 65534, 1)).unwrap_err(),
+                   VolumeTooLarge { width: 65535, height: 65535, length: 2 });
+        assert_eq!(world.copy(BlockPosition::new(-1, 0, 0), BlockPosition::new(65533, 0, 0)).unwrap().width(), 65535);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::{BlockPosition, BlockState, ChunkData, ChunkPosition};
+use crate::anvil::AnvilError;
+use crate::dat::ChunkLoadError;
+use crate::registry::{BlockRegistry, RegistryState};
+
+/// Height of a chunk in blocks, 24 sections of 16.
+pub const WORLD_HEIGHT: u32 = 24 * 16;
+
+#[derive(Debug)]
+pub enum WorldLoadError {
+    Io(std::io::Error),
+    Chunk { path: PathBuf, error: ChunkLoadError },
+    Region { path: PathBuf, error: AnvilError },
+}
+
+impl Display for WorldLoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Chunk { path, error } => write!(f, "{}: {}", path.display(), error),
+            Self::Region { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for WorldLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Chunk { error, .. } => Some(error),
+            Self::Region { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for WorldLoadError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Parses the chunk coordinates out of a `{x}_{z}.dat` file name.
+pub fn parse_chunk_file_name(path: &Path) -> Option<ChunkPosition> {
+    if path.extension()? != "dat" {
+        return None;
+    }
+    let (x, z) = path.file_stem()?.to_str()?.split_once('_')?;
+    Some(ChunkPosition::new(x.parse().ok()?, z.parse().ok()?))
+}
+
+/// `S` is the palette state of the chunks, see [`Nodes`](crate::Nodes).
+#[derive(Debug)]
+pub struct World<S: Ord = BlockState> {
+    chunks: HashMap<ChunkPosition, ChunkData<S>>,
+}
+
+impl<S: Ord> Default for World<S> {
+    fn default() -> Self {
+        Self { chunks: HashMap::new() }
+    }
+}
+
+impl World {
+    /// Loads every `{x}_{z}.dat` file in `dir`, in parallel. Other files are ignored.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, WorldLoadError> {
+        Self::load_with(dir, |path| ChunkData::read_dat(path))
+    }
+}
+
+impl World<RegistryState> {
+    /// Like [`World::load`], for dumps with ids the [`Block`](crate::Block) enum lacks.
+    #[allow(dead_code)]
+    pub fn load_in(dir: impl AsRef<Path>, registry: &BlockRegistry) -> Result<Self, WorldLoadError> {
+        Self::load_with(dir, |path| ChunkData::read_dat_in(path, registry))
+    }
+}
+
+impl<S: Ord + Send> World<S> {
+    fn load_with(dir: impl AsRef<Path>, read: impl Fn(&Path) -> Result<ChunkData<S>, ChunkLoadError> + Sync) -> Result<Self, WorldLoadError> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if let Some(pos) = parse_chunk_file_name(&path) {
+                files.push((pos, path));
+            }
+        }
+
+        let chunks = files.into_par_iter()
+            .map(|(pos, path)| match read(&path) {
+                Ok(chunk) => Ok((pos, chunk)),
+                Err(error) => Err(WorldLoadError::Chunk { path, error }),
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(Self { chunks })
+    }
+}
+
+impl<S: Clone + Ord> World<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn chunk(&self, pos: ChunkPosition) -> Option<&ChunkData<S>> {
+        self.chunks.get(&pos)
+    }
+
+    pub fn chunk_mut(&mut self, pos: ChunkPosition) -> Option<&mut ChunkData<S>> {
+        self.chunks.get_mut(&pos)
+    }
+
+    pub fn insert(&mut self, pos: ChunkPosition, chunk: ChunkData<S>) -> Option<ChunkData<S>> {
+        self.chunks.insert(pos, chunk)
+    }
+
+    #[allow(dead_code)]
+    pub fn remove(&mut self, pos: ChunkPosition) -> Option<ChunkData<S>> {
+        self.chunks.remove(&pos)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item=(&ChunkPosition, &ChunkData<S>)> {
+        self.chunks.iter()
+    }
+
+    /// Returns `None` if the chunk is not loaded or `pos` is above the world.
+    pub fn get(&self, pos: BlockPosition) -> Option<S> {
+        if pos.y as u32 >= WORLD_HEIGHT {
+            return None;
+        }
+        self.chunk(pos.into()).map(|chunk| chunk.get(pos.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, LocalBlockPosition};
+    use crate::dat::DatFormat;
+
+    #[test]
+    fn chunks() {
+        let mut world = World::new();
+        assert!(world.is_empty());
+        world.insert(ChunkPosition::new(-1, 2), ChunkData::empty());
+        world.chunk_mut(ChunkPosition::new(-1, 2)).unwrap().set_block(LocalBlockPosition::new(15, 383, 0), BlockState::from(Block::Stone));
+
+        assert_eq!(world.get(BlockPosition::new(-1, 383, 32)), Some(BlockState::from(Block::Stone)));
+        assert_eq!(world.get(BlockPosition::new(-1, 384, 32)), None);
+        assert_eq!(world.get(BlockPosition::new(0, 0, 32)), None);
+        assert!(world.remove(ChunkPosition::new(-1, 2)).is_some());
+        assert!(world.is_empty());
+    }
+
+    #[test]
+    fn load() {
+        let dir = crate::tests::temp_dir("world-load");
+        let mut chunk = ChunkData::empty();
+        chunk.set_block(LocalBlockPosition::new(1, 2, 3), BlockState::from(Block::Dirt));
+        chunk.write_dat(dir.join("3_-4.dat"), DatFormat::U16).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a chunk").unwrap();
+
+        let world = World::load(&dir).unwrap();
+        assert_eq!(world.len(), 1);
+        assert_eq!(world.get(BlockPosition::new(48 + 1, 2, -64 + 3)), Some(BlockState::from(Block::Dirt)));
+        let world = World::load_in(&dir, &BlockRegistry::vanilla()).unwrap();
+        assert_eq!(world.get(BlockPosition::new(48 + 1, 2, -64 + 3)), Some(RegistryState::from(BlockState::from(Block::Dirt))));
+
+        std::fs::write(dir.join("0_0.dat"), [0; 3]).unwrap();
+        assert!(matches!(World::load(&dir), Err(WorldLoadError::Chunk { error: ChunkLoadError::Truncated { len: 3 }, .. })));
+    }
+}